@@ -0,0 +1,91 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::{Distribution, Exp};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Names of supported arrival models
+pub const ARRIVAL_MODELS: [&str; 3] = ["constant", "poisson", "bursty"];
+
+/// How intended send times of requests are spread when sending at a fixed rate
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arrival {
+    /// Requests are evenly spaced
+    Constant,
+
+    /// Time between requests is exponentially distributed, as with independent clients
+    Poisson,
+
+    /// Requests are sent in bursts of this size, keeping the average rate
+    Bursty(u64),
+}
+
+pub type SharedSchedule = Arc<Mutex<Schedule>>;
+
+/// Hands out requests of a run to virtual users along with their intended send times
+pub struct Schedule {
+    /// Number of requests to send, 0 for infinite
+    total: u64,
+
+    /// Number of requests handed out so far
+    next: u64,
+
+    /// Requests per second and their arrival model, if sent at a fixed rate
+    rate: Option<(f64, Arrival)>,
+
+    /// Intended send time of the last request handed out in Poisson model
+    last: Duration,
+
+    rng: StdRng,
+}
+
+/// Returns a random number generator seeded with supplied seed so runs can be reproduced,
+/// from entropy otherwise
+pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+    seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+}
+
+impl Schedule {
+    pub fn new(total: u64, rate: Option<(f64, Arrival)>, seed: Option<u64>) -> Schedule {
+        Schedule {
+            total,
+            next: 0,
+            rate,
+            last: Duration::ZERO,
+            rng: seeded_rng(seed),
+        }
+    }
+
+    /// Returns true once all the requests are handed out
+    pub fn is_finished(&self) -> bool {
+        self.total != 0 && self.next >= self.total
+    }
+
+    /// Returns intended send time of the next request since start of the run, `None` inside if
+    /// requests are sent as fast as possible. Returns `None` once all the requests are handed out.
+    pub fn next(&mut self) -> Option<Option<Duration>> {
+        if self.is_finished() {
+            return None;
+        }
+        let number = self.next;
+        self.next += 1;
+
+        let (rate, arrival) = match &self.rate {
+            Some(rate) => rate,
+            None => return Some(None),
+        };
+        let intended = match arrival {
+            Arrival::Constant => Duration::from_secs_f64(number as f64 / rate),
+            Arrival::Poisson => {
+                // First request is sent right away, like in other models
+                if number > 0 {
+                    let gap = Exp::new(*rate).unwrap().sample(&mut self.rng);
+                    self.last += Duration::from_secs_f64(gap);
+                }
+                self.last
+            }
+            Arrival::Bursty(size) => Duration::from_secs_f64((number / size * size) as f64 / rate),
+        };
+        Some(Some(intended))
+    }
+}
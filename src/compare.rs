@@ -0,0 +1,162 @@
+use crate::output_producers::json_producer::JSON_SCHEMA_VERSION;
+use anyhow::{anyhow, Context};
+use prettytable::{Cell, Row, Table};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Allowed worsening of results before it's considered a regression
+#[derive(Debug, Clone)]
+pub struct Tolerances {
+    /// Increase of latency statistics in percent
+    pub latency: f64,
+
+    /// Decrease of requests per second in percent
+    pub rps: f64,
+
+    /// Increase of error rate in percentage points
+    pub error_rate: f64,
+}
+
+/// A metric compared between two runs
+struct Metric {
+    name: &'static str,
+    pointer: &'static str,
+
+    /// True if higher value is better, e.g. requests per second
+    higher_is_better: bool,
+}
+
+const METRICS: [Metric; 8] = [
+    Metric {
+        name: "rps",
+        pointer: "/totals/rps",
+        higher_is_better: true,
+    },
+    Metric {
+        name: "error_rate",
+        pointer: "/totals/error_rate",
+        higher_is_better: false,
+    },
+    Metric {
+        name: "mean (ms)",
+        pointer: "/latency/mean",
+        higher_is_better: false,
+    },
+    Metric {
+        name: "p50 (ms)",
+        pointer: "/latency/p50",
+        higher_is_better: false,
+    },
+    Metric {
+        name: "p90 (ms)",
+        pointer: "/latency/p90",
+        higher_is_better: false,
+    },
+    Metric {
+        name: "p95 (ms)",
+        pointer: "/latency/p95",
+        higher_is_better: false,
+    },
+    Metric {
+        name: "p99 (ms)",
+        pointer: "/latency/p99",
+        higher_is_better: false,
+    },
+    Metric {
+        name: "max (ms)",
+        pointer: "/latency/max",
+        higher_is_better: false,
+    },
+];
+
+/// Reads a result file produced by JSON output and checks its schema version
+fn read_result(path: &Path) -> anyhow::Result<Value> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read result file {}", path.display()))?;
+    let result: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse result file {}", path.display()))?;
+    match result.get("schema_version").and_then(Value::as_u64) {
+        Some(JSON_SCHEMA_VERSION) => Ok(result),
+        version => Err(anyhow!(
+            "Unsupported schema version {:?} of result file {}, expected {}",
+            version,
+            path.display(),
+            JSON_SCHEMA_VERSION
+        )),
+    }
+}
+
+/// Returns true if change of a metric from old to new value is beyond tolerances
+fn is_regression(metric: &Metric, old: f64, new: f64, tolerances: &Tolerances) -> bool {
+    if metric.name == "error_rate" {
+        return (new - old) * 100.0 > tolerances.error_rate;
+    }
+    if old == 0.0 {
+        return false;
+    }
+    let change = (new - old) / old * 100.0;
+    if metric.higher_is_better {
+        -change > tolerances.rps
+    } else {
+        change > tolerances.latency
+    }
+}
+
+/// Prints deltas between results of two runs and returns true if any metric regressed beyond
+/// supplied tolerances. Metrics missing from any of the results are reported as missing.
+pub fn compare(old_path: &Path, new_path: &Path, tolerances: &Tolerances) -> anyhow::Result<bool> {
+    let old = read_result(old_path)?;
+    let new = read_result(new_path)?;
+
+    let mut table = Table::new();
+    table.add_row(row!["Metric", "Old", "New", "Delta", "Delta %", "Status"]);
+    let mut regressed = false;
+    for metric in &METRICS {
+        let value = |result: &Value| result.pointer(metric.pointer).and_then(Value::as_f64);
+        let (old_value, new_value) = match (value(&old), value(&new)) {
+            (Some(old_value), Some(new_value)) => (old_value, new_value),
+            (old_value, new_value) => {
+                let format = |value: Option<f64>| {
+                    value.map_or("-".to_string(), |value| format!("{:.3}", value))
+                };
+                let status = match (old_value, new_value) {
+                    (None, None) => "missing in both",
+                    (None, _) => "missing in old",
+                    _ => "missing in new",
+                };
+                table.add_row(Row::new(vec![
+                    Cell::new(metric.name),
+                    Cell::new(&format(old_value)),
+                    Cell::new(&format(new_value)),
+                    Cell::new("-"),
+                    Cell::new("-"),
+                    Cell::new(status),
+                ]));
+                continue;
+            }
+        };
+        let delta = new_value - old_value;
+        let delta_percent = if old_value == 0.0 {
+            "-".to_string()
+        } else {
+            format!("{:+.2}%", delta / old_value * 100.0)
+        };
+        let status = if is_regression(metric, old_value, new_value, tolerances) {
+            regressed = true;
+            "REGRESSION"
+        } else {
+            "ok"
+        };
+        table.add_row(Row::new(vec![
+            Cell::new(metric.name),
+            Cell::new(&format!("{:.3}", old_value)),
+            Cell::new(&format!("{:.3}", new_value)),
+            Cell::new(&format!("{:+.3}", delta)),
+            Cell::new(&delta_percent),
+            Cell::new(status),
+        ]));
+    }
+    table.printstd();
+    Ok(regressed)
+}
@@ -0,0 +1,24 @@
+use anyhow::{anyhow, Context};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use std::io::{self, Read};
+
+/// Content codings that can be requested using `compression` field of Data file
+pub const SUPPORTED_ENCODINGS: [&str; 4] = ["gzip", "br", "deflate", "zstd"];
+
+/// Returns size of response body after decoding it as per its `Content-Encoding`. Multiple
+/// codings, e.g. `gzip, br`, are decoded in reverse order of their application.
+pub fn decoded_len(content_encoding: &str, body: &[u8]) -> anyhow::Result<u64> {
+    let mut decoder: Box<dyn Read + '_> = Box::new(body);
+    for encoding in content_encoding.rsplit(',') {
+        decoder = match encoding.trim().to_lowercase().as_str() {
+            "" | "identity" => decoder,
+            "gzip" | "x-gzip" => Box::new(GzDecoder::new(decoder)),
+            "deflate" => Box::new(ZlibDecoder::new(decoder)),
+            "br" => Box::new(brotli::Decompressor::new(decoder, 4096)),
+            "zstd" => Box::new(zstd::stream::read::Decoder::new(decoder)?),
+            encoding => return Err(anyhow!("Unsupported content encoding {}", encoding)),
+        };
+    }
+    io::copy(&mut decoder, &mut io::sink())
+        .with_context(|| format!("Failed to decode {} response body", content_encoding))
+}
@@ -0,0 +1,227 @@
+use crate::report::Report;
+use crate::types::RequestResult;
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Number of events waiting to be written after which requests wait for the writer
+const QUEUE_SIZE: usize = 8192;
+
+/// Size of buffer events are collected in before they're written to the file
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Outcome of a single request as written to the event log
+#[derive(Debug)]
+pub struct Event {
+    /// Time the request was sent at
+    pub timestamp: DateTime<Utc>,
+
+    /// Virtual user that sent the request
+    pub user: i32,
+
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    pub result: RequestResult,
+}
+
+impl Event {
+    /// Returns the event as a single line of JSON
+    fn to_line(&self) -> String {
+        let result = &self.result;
+        let mut line = json!({
+            "timestamp": self.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            "elapsed": result.completed_at.as_secs_f64(),
+            "user": self.user,
+            "name": self.name,
+            "method": self.method,
+            "url": self.url,
+            "status": result.status,
+            "latency_ms": result.latency.as_secs_f64() * 1000.0,
+            "ttfb_ms": result.ttfb.as_secs_f64() * 1000.0,
+            "dns_ms": result.dns.map(|dns| dns.as_secs_f64() * 1000.0),
+            "connect_ms": result.connect.map(|connect| connect.as_secs_f64() * 1000.0),
+            "tls_ms": result.tls.map(|tls| tls.as_secs_f64() * 1000.0),
+            "delay_ms": result.delay.map(|delay| delay.as_secs_f64() * 1000.0),
+            "bytes_sent": result.bytes_sent,
+            "bytes_received": result.bytes_received,
+            "bytes_decoded": result.bytes_decoded,
+            "redirects": result.redirects,
+            "error": result.error,
+        })
+        .to_string();
+        line.push('\n');
+        line
+    }
+}
+
+/// Writes an event of every request to a file in JSON lines format. Events are written by a
+/// background task through a buffer, so requests don't wait for the disk.
+pub struct EventLog {
+    sender: mpsc::Sender<Event>,
+    handle: JoinHandle<anyhow::Result<()>>,
+}
+
+impl EventLog {
+    /// Creates the event log file and starts writing events to it
+    pub async fn start(path: &Path) -> anyhow::Result<EventLog> {
+        let file = File::create(path)
+            .await
+            .with_context(|| format!("Failed to create event log {}", path.display()))?;
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        let handle = tokio::spawn(write_events(
+            BufWriter::with_capacity(BUFFER_SIZE, file),
+            path.to_owned(),
+            receiver,
+        ));
+        Ok(EventLog { sender, handle })
+    }
+
+    /// Returns a sender to queue events for writing with
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.sender.clone()
+    }
+
+    /// Waits till all the queued events are written. Senders returned by `sender` must be
+    /// dropped before. Fails if writing stopped at an error, as later events are lost.
+    pub async fn shutdown(self) -> anyhow::Result<()> {
+        drop(self.sender);
+        self.handle.await?
+    }
+}
+
+/// Writes queued events till all the senders are dropped, stops at the first error
+async fn write_events(
+    mut writer: BufWriter<File>,
+    path: PathBuf,
+    mut receiver: mpsc::Receiver<Event>,
+) -> anyhow::Result<()> {
+    while let Some(event) = receiver.recv().await {
+        writer
+            .write_all(event.to_line().as_bytes())
+            .await
+            .with_context(|| format!("Failed to write event log {}", path.display()))?;
+    }
+    writer
+        .flush()
+        .await
+        .with_context(|| format!("Failed to write event log {}", path.display()))
+}
+
+/// Events accounted when a report is rebuilt from an event log
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    /// Skip requests completed before this time since start of the run
+    pub from: Option<Duration>,
+
+    /// Skip requests completed at or after this time since start of the run
+    pub to: Option<Duration>,
+
+    /// Names of requests to account, all if empty
+    pub endpoints: Vec<String>,
+}
+
+/// Returns a duration from a field in milliseconds
+fn millis(event: &Value, field: &str) -> Option<Duration> {
+    event[field]
+        .as_f64()
+        .map(|millis| Duration::from_secs_f64(millis.max(0.0) / 1000.0))
+}
+
+/// Parses a line of event log back into time the request was sent at and its result
+fn parse_event(line: &str) -> anyhow::Result<(DateTime<Utc>, RequestResult)> {
+    let event: Value = serde_json::from_str(line)?;
+    let timestamp = event["timestamp"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Missing timestamp"))?;
+    let timestamp = DateTime::parse_from_rfc3339(timestamp)?.with_timezone(&Utc);
+    let elapsed = event["elapsed"]
+        .as_f64()
+        .ok_or_else(|| anyhow!("Missing elapsed"))?;
+    let result = RequestResult {
+        endpoint: event["name"].as_str().unwrap_or_default().to_owned(),
+        status: event["status"].as_u64().unwrap_or_default() as u16,
+        error: event["error"].as_str().map(str::to_owned),
+        redirects: event["redirects"]
+            .as_array()
+            .map(|codes| {
+                codes
+                    .iter()
+                    .filter_map(|code| code.as_u64().map(|code| code as u16))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        bytes_sent: event["bytes_sent"].as_u64().unwrap_or_default(),
+        bytes_received: event["bytes_received"].as_u64().unwrap_or_default(),
+        bytes_decoded: event["bytes_decoded"].as_u64().unwrap_or_default(),
+        latency: millis(&event, "latency_ms").unwrap_or_default(),
+        ttfb: millis(&event, "ttfb_ms").unwrap_or_default(),
+        dns: millis(&event, "dns_ms"),
+        connect: millis(&event, "connect_ms"),
+        tls: millis(&event, "tls_ms"),
+        completed_at: Duration::from_secs_f64(elapsed.max(0.0)),
+        delay: millis(&event, "delay_ms"),
+    };
+    Ok((timestamp, result))
+}
+
+/// Rebuilds aggregated results from an event log, accounting only requests passing the filter.
+/// Times in the report are relative to the start of the time window.
+pub fn read_report(
+    path: &Path,
+    filter: &EventFilter,
+    interval: Duration,
+) -> anyhow::Result<Report> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open event log {}", path.display()))?;
+    let from = filter.from.unwrap_or_default();
+    let mut report = Report {
+        interval,
+        ..Default::default()
+    };
+    let mut run_started_at = None;
+    let mut last_completed = from;
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read event log {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (timestamp, mut result) = parse_event(&line).with_context(|| {
+            format!("Invalid event at line {} of {}", number + 1, path.display())
+        })?;
+
+        // Request was sent at the start of its latency, counted from the start of the run
+        run_started_at.get_or_insert_with(|| {
+            timestamp
+                - chrono::Duration::from_std(result.completed_at.saturating_sub(result.latency))
+                    .unwrap_or_else(|_| chrono::Duration::zero())
+        });
+
+        if result.completed_at < from
+            || filter.to.is_some_and(|to| result.completed_at >= to)
+            || !(filter.endpoints.is_empty() || filter.endpoints.contains(&result.endpoint))
+        {
+            continue;
+        }
+        last_completed = last_completed.max(result.completed_at);
+        result.completed_at -= from;
+        report.record(&result);
+    }
+
+    let run_started_at =
+        run_started_at.ok_or_else(|| anyhow!("No events in {}", path.display()))?;
+    report.started_at = run_started_at
+        + chrono::Duration::from_std(from).unwrap_or_else(|_| chrono::Duration::zero());
+    let end = filter
+        .to
+        .map_or(last_completed, |to| to.min(last_completed));
+    report.finish(end.saturating_sub(from));
+    Ok(report)
+}
@@ -1,26 +1,244 @@
+use crate::arrival::{Arrival, ARRIVAL_MODELS};
+use crate::compare::Tolerances;
+use crate::compression::SUPPORTED_ENCODINGS;
+use crate::event_log::EventFilter;
 use crate::output_producers::output_producer::OutputProducer;
-use crate::output_producers::{json_producer, table_producer};
-use crate::types::{HttpMethods, OutputFormat, RequestData};
+use crate::output_producers::{
+    csv_producer, html_producer, json_producer, junit_producer, markdown_producer, table_producer,
+};
+use crate::think_time::ThinkTime;
+use crate::thresholds::get_thresholds;
+use crate::types::{
+    HttpMethods, LogOptions, Options, Output, OutputFormat, RequestData, DEFAULT_MAX_REDIRECTS,
+    ERROR_EXIT_CODE, OUTPUT_FORMATS,
+};
 use anyhow::{anyhow, Context};
-use clap::{App, Arg, ArgMatches};
-use reqwest::header::{HeaderName, HeaderValue};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use reqwest::header::{HeaderName, HeaderValue, ACCEPT_ENCODING};
+use reqwest::{NoProxy, Proxy};
 use serde_json::Value;
 use slog::{Drain, Logger};
 use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Shortest interval results can be aggregated in, so a run doesn't end up with countless
+/// intervals
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Extracts and returns all the command line parameters
-pub async fn extract_values_from_args(
-    args: ArgMatches<'_>,
-) -> anyhow::Result<(OutputFormat, String, i32)> {
+pub async fn extract_values_from_args(args: ArgMatches<'_>) -> anyhow::Result<Options> {
     // Extract user supplied values
-    let output_format = OutputFormat::from(args.value_of("format").unwrap());
+    let outputs = get_outputs(&args)?;
     let config_filename = args.value_of("config").unwrap();
     let total_requests: i32 = args
         .value_of("requests")
         .unwrap()
         .parse()
         .with_context(|| "Failed to parse `requests` argument".to_string())?;
-    Ok((output_format, config_filename.to_owned(), total_requests))
+    let users: i32 = args
+        .value_of("users")
+        .unwrap()
+        .parse()
+        .with_context(|| "Failed to parse `users` argument".to_string())?;
+    if total_requests < 0 || users < 0 {
+        return Err(anyhow!("Number of requests and users must not be negative"));
+    }
+
+    let rate = args
+        .value_of("rate")
+        .map(|rate| {
+            rate.parse::<f64>()
+                .with_context(|| "Failed to parse `rate` argument".to_string())
+        })
+        .transpose()?;
+    if rate.is_some_and(|rate| !(rate > 0.0 && rate.is_finite())) {
+        return Err(anyhow!("Rate must be more than zero"));
+    }
+
+    let arrival = match args.value_of("arrival") {
+        Some("poisson") => Arrival::Poisson,
+        Some("bursty") => {
+            let size: u64 = args
+                .value_of("burst-size")
+                .unwrap()
+                .parse()
+                .with_context(|| "Failed to parse `burst-size` argument".to_string())?;
+            if size == 0 {
+                return Err(anyhow!("Burst size must be at least 1"));
+            }
+            Arrival::Bursty(size)
+        }
+        _ => Arrival::Constant,
+    };
+
+    let interval = extract_interval(&args)?;
+
+    // Create directory for failed response bodies beforehand
+    let capture_dir = args.value_of("capture-failed").map(PathBuf::from);
+    if let Some(capture_dir) = &capture_dir {
+        fs::create_dir_all(capture_dir)
+            .with_context(|| format!("Failed to create directory {}", capture_dir.display()))?;
+    }
+
+    let metrics_listen = args
+        .value_of("metrics-listen")
+        .map(|address| {
+            address
+                .parse()
+                .with_context(|| format!("Invalid metrics address {}", address))
+        })
+        .transpose()?;
+    let metrics_linger = humantime::parse_duration(args.value_of("metrics-linger").unwrap())
+        .with_context(|| "Failed to parse `metrics-linger` argument".to_string())?;
+
+    let log_sample: u64 = args
+        .value_of("log-sample")
+        .unwrap()
+        .parse()
+        .with_context(|| "Failed to parse `log-sample` argument".to_string())?;
+    if log_sample == 0 {
+        return Err(anyhow!("Log sample must be at least 1"));
+    }
+    let log = LogOptions {
+        path: args.value_of("log-file").map(PathBuf::from),
+        level: match args.value_of("log-level").unwrap() {
+            "off" => None,
+            level => Some(
+                slog::Level::from_str(level).map_err(|_| anyhow!("Invalid log level {}", level))?,
+            ),
+        },
+        json: args.value_of("log-format") == Some("json"),
+        stderr: args.is_present("log-stderr"),
+        append: args.is_present("log-append"),
+        sample: log_sample,
+    };
+
+    Ok(Options {
+        outputs,
+        config_filename: config_filename.to_owned(),
+        total_requests,
+        users,
+        rate,
+        arrival,
+        think_time: args
+            .value_of("think-time")
+            .map(ThinkTime::parse)
+            .transpose()?,
+        seed: args
+            .value_of("seed")
+            .map(|seed| {
+                seed.parse()
+                    .with_context(|| "Failed to parse `seed` argument".to_string())
+            })
+            .transpose()?,
+        capture_dir,
+        // Progress redraws stderr in place, so it would garble log written there
+        quiet: args.is_present("quiet") || args.is_present("log-stderr"),
+        interval,
+        timeseries_csv: args.value_of("timeseries-csv").map(PathBuf::from),
+        raw_log: args.value_of("raw-log").map(PathBuf::from),
+        metrics_listen,
+        metrics_linger,
+        influx: args.value_of("influx").map(str::to_owned),
+        statsd: args.value_of("statsd").map(str::to_owned),
+        dogstatsd: args.is_present("dogstatsd"),
+        otlp_endpoint: args.value_of("otlp-endpoint").map(str::to_owned),
+        save_history: args.value_of("save-history").map(PathBuf::from),
+        event_log: args.value_of("event-log").map(PathBuf::from),
+        log,
+    })
+}
+
+/// Extracts length of intervals results are aggregated in
+pub fn extract_interval(args: &ArgMatches) -> anyhow::Result<Duration> {
+    let interval = humantime::parse_duration(args.value_of("interval").unwrap())
+        .with_context(|| "Failed to parse `interval` argument".to_string())?;
+    if interval < MIN_INTERVAL {
+        return Err(anyhow!(
+            "Interval must be at least {}",
+            humantime::format_duration(MIN_INTERVAL)
+        ));
+    }
+    Ok(interval)
+}
+
+/// Extracts outputs from `--output format[=file]` arguments. Output in `--format` is written to
+/// stdout if no output is supplied or if it's supplied explicitly along with outputs.
+fn get_outputs(args: &ArgMatches<'_>) -> anyhow::Result<Vec<Output>> {
+    let mut outputs = args
+        .values_of("output")
+        .into_iter()
+        .flatten()
+        .map(|output| {
+            let (format, path) = match output.split_once('=') {
+                Some((format, path)) => (format, Some(PathBuf::from(path))),
+                None => (output, None),
+            };
+            if !OUTPUT_FORMATS.contains(&format.to_lowercase().as_str()) {
+                return Err(anyhow!(
+                    "Invalid output {}, supported formats are {}",
+                    output,
+                    OUTPUT_FORMATS.join(", ")
+                ));
+            }
+            Ok(Output {
+                format: OutputFormat::from(format),
+                path,
+            })
+        })
+        .collect::<anyhow::Result<Vec<Output>>>()?;
+
+    if outputs.is_empty() || args.occurrences_of("format") > 0 {
+        outputs.push(Output {
+            format: OutputFormat::from(args.value_of("format").unwrap()),
+            path: None,
+        });
+    }
+    if outputs
+        .iter()
+        .filter(|output| output.path.is_none())
+        .count()
+        > 1
+    {
+        return Err(anyhow!("Only one output can be written to stdout"));
+    }
+    Ok(outputs)
+}
+
+/// Extracts tolerances of `compare` subcommand
+pub fn extract_tolerances(args: &ArgMatches<'_>) -> anyhow::Result<Tolerances> {
+    let tolerance = |name: &str| -> anyhow::Result<f64> {
+        args.value_of(name)
+            .unwrap()
+            .parse()
+            .with_context(|| format!("Failed to parse `{}` argument", name))
+    };
+    Ok(Tolerances {
+        latency: tolerance("latency-tolerance")?,
+        rps: tolerance("rps-tolerance")?,
+        error_rate: tolerance("error-rate-tolerance")?,
+    })
+}
+
+/// Extracts time window and endpoints of `report` subcommand
+pub fn extract_event_filter(args: &ArgMatches<'_>) -> anyhow::Result<EventFilter> {
+    let time = |name: &str| -> anyhow::Result<Option<Duration>> {
+        args.value_of(name)
+            .map(humantime::parse_duration)
+            .transpose()
+            .with_context(|| format!("Failed to parse `{}` argument", name))
+    };
+    Ok(EventFilter {
+        from: time("from")?,
+        to: time("to")?,
+        endpoints: args
+            .values_of("endpoint")
+            .map(|endpoints| endpoints.map(str::to_owned).collect())
+            .unwrap_or_default(),
+    })
 }
 
 /// Reads and parses Data file and returns RequestData struct with values fufilled
@@ -30,7 +248,7 @@ pub async fn get_request_data_from_file(config_filename: &str) -> anyhow::Result
         serde_json::from_str("{}").with_context(|| "Failed to parse default value".to_string())?;
 
     // TODO: Make error handling compact
-    let config_data = fs::read_to_string(&config_filename)
+    let config_data = fs::read_to_string(config_filename)
         .with_context(|| format!("Failed to read config file {}", &config_filename))?;
 
     let content: Value = serde_json::from_str(&config_data)
@@ -40,6 +258,7 @@ pub async fn get_request_data_from_file(config_filename: &str) -> anyhow::Result
     let actual_payload = content.get("payload").unwrap_or(&default_value);
     let mut request_data = RequestData {
         payload: actual_payload.clone(),
+        config: content.clone(),
         ..Default::default()
     };
 
@@ -80,6 +299,13 @@ pub async fn get_request_data_from_file(config_filename: &str) -> anyhow::Result
         .unwrap()
         .to_owned();
 
+    // Extract name or use URL as a name
+    request_data.name = content
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or(&request_data.url)
+        .to_owned();
+
     // Extract cert_path if spplied
     request_data.cert_path = content
         .get("ssl_cert")
@@ -88,23 +314,148 @@ pub async fn get_request_data_from_file(config_filename: &str) -> anyhow::Result
         .unwrap_or("")
         .to_owned();
 
+    // Extract proxy settings if supplied
+    request_data.proxies = get_proxies(content.get("proxy"))?;
+
+    // Extract redirect policy if supplied
+    request_data.max_redirects = match content.get("redirects") {
+        None => DEFAULT_MAX_REDIRECTS,
+        Some(Value::String(policy)) if policy == "none" => 0,
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| anyhow!("Invalid redirects {}, use a number or \"none\"", value))?
+            as usize,
+    };
+
+    // Keep cookies per virtual user if asked
+    request_data.cookies = content
+        .get("cookies")
+        .unwrap_or(&Value::Bool(false))
+        .as_bool()
+        .ok_or_else(|| anyhow!("Invalid cookies, use true or false"))?;
+
+    // Ask for compressed responses if supplied
+    if let Some(encodings) = content.get("compression") {
+        let encodings = encodings
+            .as_array()
+            .ok_or_else(|| anyhow!("Invalid compression {}, use a list of encodings", encodings))?
+            .iter()
+            .map(|encoding| match encoding.as_str() {
+                Some(encoding) if SUPPORTED_ENCODINGS.contains(&encoding) => Ok(encoding),
+                _ => Err(anyhow!(
+                    "Invalid compression {}, supported values are {}",
+                    encoding,
+                    SUPPORTED_ENCODINGS.join(", ")
+                )),
+            })
+            .collect::<anyhow::Result<Vec<&str>>>()?;
+        request_data.headers.insert(
+            ACCEPT_ENCODING,
+            HeaderValue::from_str(&encodings.join(", ")).unwrap(),
+        );
+    }
+
+    // Send trace context with requests if asked
+    request_data.trace = content
+        .get("trace")
+        .unwrap_or(&Value::Bool(false))
+        .as_bool()
+        .ok_or_else(|| anyhow!("Invalid trace, use true or false"))?;
+
+    // Extract thresholds to check results against if supplied
+    request_data.thresholds = get_thresholds(content.get("thresholds"))?;
+
     Ok(request_data)
 }
 
-/// Returns a new logger
-pub async fn get_logger(filename: &str) -> anyhow::Result<Logger> {
+/// Builds proxies from `proxy` section of Data file. The section is either a single proxy URL
+/// used for all requests or an object containing `http`, `https` and/or `all` proxy URLs with
+/// optional `username`, `password` and `no_proxy` list.
+fn get_proxies(proxy_config: Option<&Value>) -> anyhow::Result<Vec<Proxy>> {
+    let proxy_config = match proxy_config {
+        None => return Ok(vec![]),
+        Some(Value::String(url)) => {
+            let proxy = Proxy::all(url).with_context(|| format!("Invalid proxy {}", url))?;
+            return Ok(vec![proxy]);
+        }
+        Some(Value::Object(proxy_config)) => proxy_config,
+        Some(_) => return Err(anyhow!("Invalid proxy configuration")),
+    };
+
+    let username = proxy_config.get("username").and_then(Value::as_str);
+    let password = proxy_config
+        .get("password")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let no_proxy = proxy_config
+        .get("no_proxy")
+        .and_then(Value::as_array)
+        .map(|hosts| {
+            hosts
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<&str>>()
+                .join(",")
+        });
+
+    // Scheme specific proxies come first as reqwest uses the first matching proxy
+    let mut proxies = vec![];
+    for scheme in &["http", "https", "all"] {
+        let url = match proxy_config.get(*scheme).and_then(Value::as_str) {
+            Some(url) => url,
+            None => continue,
+        };
+        let mut proxy = match *scheme {
+            "http" => Proxy::http(url),
+            "https" => Proxy::https(url),
+            _ => Proxy::all(url),
+        }
+        .with_context(|| format!("Invalid {} proxy {}", scheme, url))?;
+
+        if let Some(username) = username {
+            proxy = proxy.basic_auth(username, password);
+        }
+        proxy = proxy.no_proxy(no_proxy.as_deref().and_then(NoProxy::from_string));
+        proxies.push(proxy);
+    }
+    Ok(proxies)
+}
+
+/// Returns a logger as per log options. Log is written to `default_path` unless another file or
+/// stderr is supplied.
+pub async fn get_logger(options: &LogOptions, default_path: &str) -> anyhow::Result<Logger> {
+    let level = match options.level {
+        Some(level) => level,
+        None => return Ok(slog::Logger::root(slog::Discard, o!())),
+    };
+
     // Create a logger instance
-    let file = fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(filename)
-        .with_context(|| format!("Failed to create log file {}", filename))?;
-    let decorator = slog_term::PlainDecorator::new(file);
-    let drain = slog_async::Async::new(slog_term::FullFormat::new(decorator).build().fuse())
-        .build()
-        .fuse();
-    Ok(slog::Logger::root(drain, o!()))
+    let out: Box<dyn Write + Send> = if options.stderr {
+        Box::new(std::io::stderr())
+    } else {
+        let path = options
+            .path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(default_path));
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(options.append)
+            .truncate(!options.append)
+            .open(&path)
+            .with_context(|| format!("Failed to create log file {}", path.display()))?;
+        Box::new(file)
+    };
+    let drain = if options.json {
+        slog_async::Async::new(slog_json::Json::default(out).fuse()).build()
+    } else {
+        let decorator = slog_term::PlainDecorator::new(out);
+        slog_async::Async::new(slog_term::FullFormat::new(decorator).build().fuse()).build()
+    };
+    Ok(slog::Logger::root(
+        slog::LevelFilter::new(drain, level).fuse(),
+        o!(),
+    ))
 }
 
 /// Specifies all the command line arguments. Constructs a
@@ -128,9 +479,19 @@ pub async fn get_cmd_args<'a>() -> ArgMatches<'a> {
                 .value_name("format")
                 .help("Output format")
                 .takes_value(true)
-                .possible_values(&["table", "json"])
+                .possible_values(&OUTPUT_FORMATS)
                 .default_value("table")
         )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("format[=file]")
+                .help("Output to produce, written to file if supplied or stdout otherwise. Can be repeated")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
         .arg (
             Arg::with_name("requests")
                 .short("n")
@@ -139,7 +500,297 @@ pub async fn get_cmd_args<'a>() -> ArgMatches<'a> {
                 .value_name("total_requests")
                     .help("Number of requests to send. Supply 0 or avoid supplying to send infinite number of requests")
         )
-        .get_matches()
+        .arg(
+            Arg::with_name("users")
+                .short("u")
+                .long("users")
+                .default_value("0")
+                .value_name("users")
+                .help("Number of virtual users sending requests one after another. Supply 0 or avoid supplying to use a user per request")
+        )
+        .arg(
+            Arg::with_name("rate")
+                .long("rate")
+                .value_name("rps")
+                .takes_value(true)
+                .help("Requests per second to send at. Latency is also reported from intended send times, so stalls aren't hidden")
+        )
+        .arg(
+            Arg::with_name("arrival")
+                .long("arrival")
+                .value_name("model")
+                .possible_values(&ARRIVAL_MODELS)
+                .requires("rate")
+                .help("How send times are spread at a fixed rate: evenly, with exponential gaps like independent clients, or in bursts. Default is constant")
+        )
+        .arg(
+            Arg::with_name("burst-size")
+                .long("burst-size")
+                .value_name("requests")
+                .default_value("10")
+                .help("Number of requests sent together in bursty arrival model, bursts are spaced to keep the rate")
+        )
+        .arg(
+            Arg::with_name("think-time")
+                .long("think-time")
+                .value_name("time")
+                .takes_value(true)
+                .help("Time a user waits between its requests: a duration like 1s, uniform:1s,3s, normal:2s,500ms (mean, standard deviation) or exponential:2s (mean)")
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("number")
+                .takes_value(true)
+                .help("Seed of random arrival gaps and think times, so they're the same in every run")
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .help("Don't show live progress during the run")
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("duration")
+                .default_value("1s")
+                .help("Length of intervals results are aggregated in, e.g. 1s or 500ms")
+        )
+        .arg(
+            Arg::with_name("timeseries-csv")
+                .long("timeseries-csv")
+                .value_name("file")
+                .takes_value(true)
+                .help("CSV file to write results per interval to")
+        )
+        .arg(
+            Arg::with_name("raw-log")
+                .long("raw-log")
+                .value_name("file")
+                .takes_value(true)
+                .help("CSV file to write result of every request to as it completes")
+        )
+        .arg(
+            Arg::with_name("log-file")
+                .long("log-file")
+                .value_name("file")
+                .takes_value(true)
+                .help("File to write log to, stresster.log by default")
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .value_name("level")
+                .possible_values(&["off", "critical", "error", "warning", "info", "debug", "trace"])
+                .default_value("info")
+                .help("Lowest level of records written to log, off disables logging")
+        )
+        .arg(
+            Arg::with_name("log-format")
+                .long("log-format")
+                .value_name("format")
+                .possible_values(&["plain", "json"])
+                .default_value("plain")
+                .help("Format of log records")
+        )
+        .arg(
+            Arg::with_name("log-stderr")
+                .long("log-stderr")
+                .conflicts_with("log-file")
+                .help("Write log to stderr instead of a file, live progress isn't shown then")
+        )
+        .arg(
+            Arg::with_name("log-append")
+                .long("log-append")
+                .help("Append to log file instead of truncating it")
+        )
+        .arg(
+            Arg::with_name("log-sample")
+                .long("log-sample")
+                .value_name("n")
+                .default_value("1")
+                .help("Write per request records for 1 in n requests, errors are always written")
+        )
+        .arg(
+            Arg::with_name("event-log")
+                .long("event-log")
+                .value_name("file")
+                .takes_value(true)
+                .help("JSON lines file to write an event of every request to, replaces per request lines in log")
+        )
+        .arg(
+            Arg::with_name("capture-failed")
+                .long("capture-failed")
+                .value_name("directory")
+                .takes_value(true)
+                .help("Directory to write bodies of failed (4xx and 5xx) responses to")
+        )
+        .arg(
+            Arg::with_name("metrics-listen")
+                .long("metrics-listen")
+                .value_name("address")
+                .takes_value(true)
+                .help("Address to serve live metrics in Prometheus format at, e.g. 127.0.0.1:9464")
+        )
+        .arg(
+            Arg::with_name("metrics-linger")
+                .long("metrics-linger")
+                .value_name("duration")
+                .takes_value(true)
+                .default_value("15s")
+                .help("Time to keep serving metrics after the run, so final results can be scraped")
+        )
+        .arg(
+            Arg::with_name("influx")
+                .long("influx")
+                .value_name("url")
+                .takes_value(true)
+                .help("InfluxDB to push results per interval to in line protocol, e.g. udp://127.0.0.1:8089 or http://127.0.0.1:8086/write?db=stresster")
+        )
+        .arg(
+            Arg::with_name("statsd")
+                .long("statsd")
+                .value_name("address")
+                .takes_value(true)
+                .help("StatsD address to push results per interval to over UDP, e.g. 127.0.0.1:8125")
+        )
+        .arg(
+            Arg::with_name("dogstatsd")
+                .long("dogstatsd")
+                .requires("statsd")
+                .help("Add DogStatsD tags to StatsD metrics")
+        )
+        .arg(
+            Arg::with_name("otlp-endpoint")
+                .long("otlp-endpoint")
+                .value_name("url")
+                .takes_value(true)
+                .help("OpenTelemetry collector to export client spans to over OTLP/HTTP, e.g. http://127.0.0.1:4318")
+        )
+        .arg(
+            Arg::with_name("save-history")
+                .long("save-history")
+                .value_name("database")
+                .takes_value(true)
+                .help("SQLite database to record config, environment and results of the run in")
+        )
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("compare")
+                .about("Compares two JSON result files and exits with 1 if results regressed")
+                .arg(Arg::with_name("old").required(true).help("Result file of baseline run"))
+                .arg(Arg::with_name("new").required(true).help("Result file of new run"))
+                .arg(
+                    Arg::with_name("latency-tolerance")
+                        .long("latency-tolerance")
+                        .value_name("percent")
+                        .default_value("10")
+                        .help("Allowed increase of latency statistics in percent")
+                )
+                .arg(
+                    Arg::with_name("rps-tolerance")
+                        .long("rps-tolerance")
+                        .value_name("percent")
+                        .default_value("10")
+                        .help("Allowed decrease of requests per second in percent")
+                )
+                .arg(
+                    Arg::with_name("error-rate-tolerance")
+                        .long("error-rate-tolerance")
+                        .value_name("points")
+                        .default_value("1")
+                        .help("Allowed increase of error rate in percentage points")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Rebuilds results from an event log written with --event-log")
+                .arg(Arg::with_name("events").required(true).help("Event log file"))
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("format")
+                        .possible_values(&OUTPUT_FORMATS)
+                        .default_value("table")
+                        .help("Output format")
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("duration")
+                        .takes_value(true)
+                        .help("Skip requests completed before this time since start of the run, e.g. 5m")
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("duration")
+                        .takes_value(true)
+                        .help("Skip requests completed at or after this time since start of the run, e.g. 1h")
+                )
+                .arg(
+                    Arg::with_name("endpoint")
+                        .long("endpoint")
+                        .value_name("name")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Account only requests with this name, can be supplied multiple times")
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .value_name("duration")
+                        .default_value("1s")
+                        .help("Length of intervals results are aggregated in, e.g. 1s or 500ms")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Shows runs recorded with --save-history")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("Lists recorded runs, latest first")
+                        .arg(Arg::with_name("database").required(true).help("History database"))
+                        .arg(
+                            Arg::with_name("target")
+                                .long("target")
+                                .value_name("url")
+                                .takes_value(true)
+                                .help("Show only runs against this URL")
+                        )
+                        .arg(
+                            Arg::with_name("limit")
+                                .long("limit")
+                                .value_name("runs")
+                                .default_value("20")
+                                .help("Maximum number of runs to show")
+                        )
+                )
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("Shows a recorded run")
+                        .arg(Arg::with_name("database").required(true).help("History database"))
+                        .arg(Arg::with_name("id").required(true).help("Id of the run"))
+                        .arg(
+                            Arg::with_name("json")
+                                .long("json")
+                                .help("Print whole JSON result of the run")
+                        )
+                )
+        )
+        .get_matches_safe()
+        .unwrap_or_else(|e| {
+            // Help and version are printed as usual, while usage errors exit like other errors
+            if e.use_stderr() {
+                eprintln!("{}", e.message);
+                std::process::exit(ERROR_EXIT_CODE.into());
+            }
+            e.exit()
+        })
 }
 
 /// Returns an output producer based on `OutputFormat`
@@ -147,5 +798,9 @@ pub async fn get_output_producer(output_format: OutputFormat) -> Box<dyn OutputP
     match output_format {
         OutputFormat::Json => Box::new(json_producer::JSONProducer {}),
         OutputFormat::Table => Box::new(table_producer::TableProducer {}),
+        OutputFormat::Csv => Box::new(csv_producer::CSVProducer {}),
+        OutputFormat::Html => Box::new(html_producer::HTMLProducer {}),
+        OutputFormat::Junit => Box::new(junit_producer::JUnitProducer {}),
+        OutputFormat::Markdown => Box::new(markdown_producer::MarkdownProducer {}),
     }
 }
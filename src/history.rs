@@ -0,0 +1,232 @@
+use crate::output_producers::json_producer::report_json;
+use crate::redact::{redact_config, redact_url, redact_urls};
+use crate::report::Report;
+use anyhow::{anyhow, Context};
+use prettytable::{Cell, Row, Table};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Opens an existing history database read-only
+fn open(path: &Path) -> anyhow::Result<Connection> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open history database {}", path.display()))
+}
+
+/// Opens history database and creates its schema if it doesn't exist yet
+fn create(path: &Path) -> anyhow::Result<Connection> {
+    let connection = Connection::open(path)
+        .with_context(|| format!("Failed to open history database {}", path.display()))?;
+    connection
+        .execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at TEXT NOT NULL,
+                duration REAL NOT NULL,
+                target TEXT NOT NULL,
+                config_hash TEXT NOT NULL,
+                config TEXT NOT NULL,
+                environment TEXT NOT NULL,
+                requests INTEGER NOT NULL,
+                failed INTEGER NOT NULL,
+                error_rate REAL NOT NULL,
+                rps REAL NOT NULL,
+                mean REAL NOT NULL,
+                p50 REAL NOT NULL,
+                p90 REAL NOT NULL,
+                p95 REAL NOT NULL,
+                p99 REAL NOT NULL,
+                max REAL NOT NULL,
+                result TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS runs_target ON runs (target, started_at);",
+        )
+        .with_context(|| format!("Failed to create history database {}", path.display()))?;
+    Ok(connection)
+}
+
+/// Returns details of the environment stresster runs in, with credentials in arguments masked
+fn environment() -> Value {
+    json!({
+        "stresster_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "arguments": std::env::args().map(|argument| redact_url(&argument)).collect::<Vec<String>>(),
+    })
+}
+
+/// History database runs are recorded in, opened before the run so a wrong path fails early
+pub struct History {
+    path: PathBuf,
+    connection: Connection,
+}
+
+impl History {
+    /// Opens history database, creating it and its schema if it doesn't exist yet
+    pub fn create(path: &Path) -> anyhow::Result<History> {
+        Ok(History {
+            path: path.to_owned(),
+            connection: create(path)?,
+        })
+    }
+
+    /// Records config, environment and aggregated results of a run and returns id of the run.
+    /// Secrets in config and arguments are masked before they're stored.
+    pub fn save_run(&self, report: &Report) -> anyhow::Result<i64> {
+        save_run(&self.connection, report).with_context(|| {
+            format!(
+                "Failed to save run to history database {}",
+                self.path.display()
+            )
+        })
+    }
+}
+
+/// Records a run in supplied database and returns id of the run
+fn save_run(connection: &Connection, report: &Report) -> anyhow::Result<i64> {
+    let result = redact_urls(&report_json(report));
+    let number = |pointer: &str| {
+        result
+            .pointer(pointer)
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0)
+    };
+    let text = |pointer: &str| {
+        result
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    };
+    connection.execute(
+        "INSERT INTO runs (started_at, duration, target, config_hash, config, environment,
+                requests, failed, error_rate, rps, mean, p50, p90, p95, p99, max, result)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            text("/metadata/started_at"),
+            number("/metadata/duration"),
+            text("/metadata/target/url"),
+            text("/metadata/config_hash"),
+            redact_config(&report.config).to_string(),
+            environment().to_string(),
+            report.completed() as i64,
+            report.failed() as i64,
+            number("/totals/error_rate"),
+            number("/totals/rps"),
+            number("/latency/mean"),
+            number("/latency/p50"),
+            number("/latency/p90"),
+            number("/latency/p95"),
+            number("/latency/p99"),
+            number("/latency/max"),
+            result.to_string(),
+        ],
+    )?;
+    Ok(connection.last_insert_rowid())
+}
+
+/// Prints runs recorded in history, latest first, optionally only of supplied target
+pub fn list_runs(path: &Path, target: Option<&str>, limit: u32) -> anyhow::Result<()> {
+    let connection = open(path)?;
+    let mut statement = connection.prepare(
+        "SELECT id, started_at, target, requests, rps, error_rate, p50, p95, p99
+         FROM runs WHERE ?1 IS NULL OR target = ?1 ORDER BY id DESC LIMIT ?2",
+    )?;
+    let mut rows = statement.query(params![target, limit])?;
+
+    let mut table = Table::new();
+    table.add_row(row![
+        "Id",
+        "Started At",
+        "Target",
+        "Requests",
+        "RPS",
+        "Error Rate",
+        "p50 (ms)",
+        "p95 (ms)",
+        "p99 (ms)"
+    ]);
+    while let Some(row) = rows.next()? {
+        table.add_row(Row::new(vec![
+            Cell::new(&row.get::<_, i64>(0)?.to_string()),
+            Cell::new(&row.get::<_, String>(1)?),
+            Cell::new(&row.get::<_, String>(2)?),
+            Cell::new(&row.get::<_, i64>(3)?.to_string()),
+            Cell::new(&format!("{:.2}", row.get::<_, f64>(4)?)),
+            Cell::new(&format!("{:.2}%", row.get::<_, f64>(5)? * 100.0)),
+            Cell::new(&format!("{:.3}", row.get::<_, f64>(6)?)),
+            Cell::new(&format!("{:.3}", row.get::<_, f64>(7)?)),
+            Cell::new(&format!("{:.3}", row.get::<_, f64>(8)?)),
+        ]));
+    }
+    table.printstd();
+    Ok(())
+}
+
+/// Prints a run recorded in history. Whole JSON result is printed if asked, so it can be used
+/// with `compare` subcommand.
+pub fn show_run(path: &Path, id: i64, json: bool) -> anyhow::Result<()> {
+    let connection = open(path)?;
+    let run = connection
+        .query_row(
+            "SELECT config, environment, result FROM runs WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            },
+        )
+        .optional()?;
+    let (config, environment, result) =
+        run.ok_or_else(|| anyhow!("Run {} not found in {}", id, path.display()))?;
+    let result: Value = serde_json::from_str(&result).context("Failed to parse saved result")?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    let value = |pointer: &str| match result.pointer(pointer) {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Number(value)) => value.to_string(),
+        _ => "-".to_string(),
+    };
+    for (name, pointer) in &[
+        ("Id", ""),
+        ("Started At", "/metadata/started_at"),
+        ("Finished At", "/metadata/finished_at"),
+        ("Duration (s)", "/metadata/duration"),
+        ("Target", "/metadata/target/url"),
+        ("Config Hash", "/metadata/config_hash"),
+        ("Requests", "/totals/requests"),
+        ("Failed", "/totals/failed"),
+        ("Error Rate", "/totals/error_rate"),
+        ("RPS", "/totals/rps"),
+        ("Mean (ms)", "/latency/mean"),
+        ("p50 (ms)", "/latency/p50"),
+        ("p90 (ms)", "/latency/p90"),
+        ("p95 (ms)", "/latency/p95"),
+        ("p99 (ms)", "/latency/p99"),
+        ("Max (ms)", "/latency/max"),
+    ] {
+        let value = if pointer.is_empty() {
+            id.to_string()
+        } else {
+            value(pointer)
+        };
+        table.add_row(row![name, value]);
+    }
+    table.printstd();
+
+    let environment: Value = serde_json::from_str(&environment).unwrap_or_default();
+    let config: Value = serde_json::from_str(&config).unwrap_or_default();
+    println!(
+        "Environment:\n{}",
+        serde_json::to_string_pretty(&environment)?
+    );
+    println!("Config:\n{}", serde_json::to_string_pretty(&config)?);
+    Ok(())
+}
@@ -7,21 +7,42 @@ extern crate slog;
 extern crate slog_async;
 extern crate slog_term;
 
+mod arrival;
+mod compare;
+mod compression;
+mod event_log;
 mod helper;
+mod history;
+mod metrics;
 pub mod output_producers;
+mod progress;
+mod push;
+mod raw_log;
+mod redact;
+mod report;
 mod stresster;
+mod think_time;
+mod thresholds;
+mod timeseries;
+mod timing;
+mod trace_context;
 mod types;
 
+use std::process::ExitCode;
 use stresster::Stresster;
+use types::ERROR_EXIT_CODE;
 
 const LOG_PATH: &str = "stresster.log";
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    Stresster {
+async fn main() -> ExitCode {
+    let result = Stresster {
         log_path: LOG_PATH.to_owned(),
     }
     .run()
-    .await?;
-    Ok(())
+    .await;
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {:?}", e);
+        ExitCode::from(ERROR_EXIT_CODE)
+    })
 }
@@ -0,0 +1,191 @@
+use crate::report::{Latency, Report};
+use crate::types::SharedReport;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Upper bounds of latency histogram buckets in seconds
+const BUCKETS: [f64; 14] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Largest request accepted by metrics endpoint
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Writes a latency distribution as a Prometheus histogram with supplied labels
+fn render_histogram(out: &mut String, name: &str, labels: &str, latency: &Latency) {
+    for bound in BUCKETS {
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}le=\"{}\"}} {}",
+            name,
+            labels,
+            bound,
+            latency.count_up_to(Duration::from_secs_f64(bound))
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{}_bucket{{{}le=\"+Inf\"}} {}",
+        name,
+        labels,
+        latency.count()
+    );
+    let labels = labels.trim_end_matches(',');
+    let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, latency.sum() / 1000.0);
+    let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, latency.count());
+}
+
+/// Escapes a label value as per Prometheus text format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Returns live counters and latency histograms of the run in Prometheus text format
+pub fn render(report: &Report, sent: u64) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP stresster_requests_sent_total Number of requests sent.\n");
+    out.push_str("# TYPE stresster_requests_sent_total counter\n");
+    let _ = writeln!(out, "stresster_requests_sent_total {}", sent);
+
+    out.push_str(
+        "# HELP stresster_requests_in_flight Number of requests waiting for a response.\n",
+    );
+    out.push_str("# TYPE stresster_requests_in_flight gauge\n");
+    let _ = writeln!(
+        out,
+        "stresster_requests_in_flight {}",
+        sent.saturating_sub(report.completed())
+    );
+
+    out.push_str("# HELP stresster_requests_total Number of completed requests per final status code, 0 if request failed.\n");
+    out.push_str("# TYPE stresster_requests_total counter\n");
+    for (name, endpoint) in &report.endpoints {
+        for (status, count) in endpoint.status_codes.iter().collect::<BTreeMap<_, _>>() {
+            let _ = writeln!(
+                out,
+                "stresster_requests_total{{endpoint=\"{}\",status=\"{}\"}} {}",
+                escape(name),
+                status,
+                count
+            );
+        }
+    }
+
+    out.push_str("# HELP stresster_errors_total Number of failed requests per kind of error.\n");
+    out.push_str("# TYPE stresster_errors_total counter\n");
+    for (kind, count) in report.errors.iter().collect::<BTreeMap<_, _>>() {
+        let _ = writeln!(
+            out,
+            "stresster_errors_total{{kind=\"{}\"}} {}",
+            escape(kind),
+            count
+        );
+    }
+
+    out.push_str(
+        "# HELP stresster_redirects_total Number of followed redirect hops per status code.\n",
+    );
+    out.push_str("# TYPE stresster_redirects_total counter\n");
+    for (status, count) in report.redirect_codes.iter().collect::<BTreeMap<_, _>>() {
+        let _ = writeln!(
+            out,
+            "stresster_redirects_total{{status=\"{}\"}} {}",
+            status, count
+        );
+    }
+
+    out.push_str("# HELP stresster_bytes_sent_total Size of request bodies sent.\n");
+    out.push_str("# TYPE stresster_bytes_sent_total counter\n");
+    let _ = writeln!(out, "stresster_bytes_sent_total {}", report.bytes_sent);
+    out.push_str(
+        "# HELP stresster_bytes_received_total Size of response bodies received on the wire.\n",
+    );
+    out.push_str("# TYPE stresster_bytes_received_total counter\n");
+    let _ = writeln!(
+        out,
+        "stresster_bytes_received_total {}",
+        report.bytes_received
+    );
+
+    out.push_str("# HELP stresster_request_duration_seconds Latency of requests including reading response body.\n");
+    out.push_str("# TYPE stresster_request_duration_seconds histogram\n");
+    for (name, endpoint) in &report.endpoints {
+        render_histogram(
+            &mut out,
+            "stresster_request_duration_seconds",
+            &format!("endpoint=\"{}\",", escape(name)),
+            &endpoint.latency,
+        );
+    }
+
+    out.push_str("# HELP stresster_phase_duration_seconds Time taken by each phase of requests.\n");
+    out.push_str("# TYPE stresster_phase_duration_seconds histogram\n");
+    for (phase, latency) in report.phases.all() {
+        render_histogram(
+            &mut out,
+            "stresster_phase_duration_seconds",
+            &format!("phase=\"{}\",", phase),
+            latency,
+        );
+    }
+    out
+}
+
+/// Serves metrics over HTTP at `/metrics` till the task is aborted
+pub async fn serve_metrics(listener: TcpListener, report: SharedReport, sent: Arc<AtomicU64>) {
+    loop {
+        if let Ok((stream, _)) = listener.accept().await {
+            let report = report.clone();
+            let sent = sent.clone();
+            tokio::spawn(async move {
+                let _ = respond(stream, report, sent).await;
+            });
+        }
+    }
+}
+
+/// Reads a single HTTP request and responds with metrics if it asks for them
+async fn respond(
+    mut stream: TcpStream,
+    report: SharedReport,
+    sent: Arc<AtomicU64>,
+) -> std::io::Result<()> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let (method, path) = (request_line.next(), request_line.next().unwrap_or_default());
+    let (status, content_type, body) = match (method, path.split('?').next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = render(&*report.lock().await, sent.load(Ordering::SeqCst));
+            ("200 OK", "text/plain; version=0.0.4; charset=utf-8", body)
+        }
+        _ => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
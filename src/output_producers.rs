@@ -1,3 +1,7 @@
+pub mod csv_producer;
+pub mod html_producer;
 pub mod json_producer;
+pub mod junit_producer;
+pub mod markdown_producer;
 pub mod output_producer;
 pub mod table_producer;
@@ -0,0 +1,98 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use std::io::Write;
+
+use crate::output_producers::output_producer;
+use crate::report::Report;
+use crate::types::{Logger, SharedReport};
+
+/// Struct that produces CSV output. Summary is written as metric,value rows.
+pub struct CSVProducer {}
+
+/// Returns summary of the run as metric,value rows
+fn summary_rows(report: &Report) -> Vec<(String, String)> {
+    let mut rows = vec![
+        ("requests".to_string(), report.completed().to_string()),
+        ("errors".to_string(), report.failed().to_string()),
+        (
+            "error_rate".to_string(),
+            format!("{:.4}", report.error_rate()),
+        ),
+        (
+            "duration_s".to_string(),
+            format!("{:.3}", report.duration.as_secs_f64()),
+        ),
+        (
+            "rps".to_string(),
+            format!("{:.3}", report.per_second(report.completed())),
+        ),
+    ];
+    rows.extend(
+        report
+            .latency
+            .summary()
+            .into_iter()
+            .map(|(label, value)| (format!("latency_{}_ms", label), format!("{:.3}", value))),
+    );
+
+    let mut status_codes = report.status_codes.iter().collect::<Vec<_>>();
+    status_codes.sort();
+    rows.extend(
+        status_codes
+            .into_iter()
+            .map(|(code, count)| (format!("status_{}", code), count.to_string())),
+    );
+
+    let mut redirect_codes = report.redirect_codes.iter().collect::<Vec<_>>();
+    redirect_codes.sort();
+    rows.extend(
+        redirect_codes
+            .into_iter()
+            .map(|(code, count)| (format!("redirect_{}", code), count.to_string())),
+    );
+
+    rows.push(("bytes_sent".to_string(), report.bytes_sent.to_string()));
+    rows.push((
+        "bytes_received".to_string(),
+        report.bytes_received.to_string(),
+    ));
+    rows.push((
+        "bytes_decompressed".to_string(),
+        report.bytes_decoded.to_string(),
+    ));
+
+    let mut errors = report.errors.iter().collect::<Vec<_>>();
+    errors.sort();
+    rows.extend(
+        errors
+            .into_iter()
+            .map(|(kind, count)| (format!("error_{}", kind), count.to_string())),
+    );
+    rows
+}
+
+#[async_trait()]
+impl output_producer::OutputProducer for CSVProducer {
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()> {
+        let logger = logger.clone();
+        debug!(logger, "Writing output in CSV format");
+        let report = report.lock().await;
+
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record(["metric", "value"])?;
+        for (metric, value) in summary_rows(&report) {
+            writer.write_record([metric, value])?;
+        }
+        writer.flush().context("Failed to write CSV output")?;
+        Ok(())
+    }
+
+    async fn format_name(&self) -> String {
+        "Csv".to_string()
+    }
+}
@@ -0,0 +1,284 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::output_producers::output_producer;
+use crate::redact::{redact_config, redact_url};
+use crate::report::Report;
+use crate::timeseries::rps;
+use crate::types::{Logger, SharedReport};
+
+/// Struct that produces a self-contained HTML report
+pub struct HTMLProducer;
+
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 240.0;
+const CHART_MARGIN: f64 = 48.0;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:800px;color:#222}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:4px 10px;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+pre{background:#f4f4f4;padding:1em;overflow-x:auto}\
+svg text{font-size:11px;fill:#555}";
+
+/// A line drawn on a chart along with its label and color
+struct Series {
+    label: &'static str,
+    color: &'static str,
+    points: Vec<(f64, f64)>,
+}
+
+/// Escapes text to be placed inside HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns an SVG line chart of supplied series. X axis is time since start in seconds.
+fn line_chart(unit: &str, series: &[Series]) -> String {
+    let points = series.iter().flat_map(|series| series.points.iter());
+    let (max_x, max_y) = points.fold((0.0_f64, 0.0_f64), |(max_x, max_y), (x, y)| {
+        (max_x.max(*x), max_y.max(*y))
+    });
+    let max_x = if max_x > 0.0 { max_x } else { 1.0 };
+    let max_y = if max_y > 0.0 { max_y * 1.1 } else { 1.0 };
+    let plot_width = CHART_WIDTH - 2.0 * CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - 2.0 * CHART_MARGIN;
+    let to_x = |x: f64| CHART_MARGIN + x / max_x * plot_width;
+    let to_y = |y: f64| CHART_HEIGHT - CHART_MARGIN - y / max_y * plot_height;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT
+    );
+
+    // Horizontal grid lines with values
+    for step in 0..=4 {
+        let value = max_y * step as f64 / 4.0;
+        let y = to_y(value);
+        let _ = write!(
+            svg,
+            r##"<line x1="{x1}" y1="{y:.1}" x2="{x2}" y2="{y:.1}" stroke="#eee"/><text x="{tx}" y="{ty:.1}" text-anchor="end">{value:.1}</text>"##,
+            x1 = CHART_MARGIN,
+            x2 = CHART_WIDTH - CHART_MARGIN,
+            y = y,
+            tx = CHART_MARGIN - 4.0,
+            ty = y + 4.0,
+            value = value
+        );
+    }
+    let _ = write!(
+        svg,
+        r#"<text x="{x}" y="16">{unit}</text><text x="{x}" y="{y}">0s</text><text x="{x2}" y="{y}" text-anchor="end">{max_x:.1}s</text>"#,
+        x = CHART_MARGIN,
+        x2 = CHART_WIDTH - CHART_MARGIN,
+        y = CHART_HEIGHT - CHART_MARGIN + 16.0,
+        unit = escape(unit),
+        max_x = max_x
+    );
+
+    for (index, series) in series.iter().enumerate() {
+        let points = series
+            .points
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", to_x(*x), to_y(*y)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = write!(
+            svg,
+            r#"<polyline fill="none" stroke="{color}" stroke-width="2" points="{points}"/><text x="{x}" y="{y}" style="fill:{color}">{label}</text>"#,
+            color = series.color,
+            points = points,
+            x = CHART_MARGIN + 80.0 * index as f64,
+            y = CHART_HEIGHT - 8.0,
+            label = escape(series.label)
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Returns an HTML table with supplied header and rows
+fn html_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut table = String::from("<table><tr>");
+    for cell in header {
+        let _ = write!(table, "<th>{}</th>", escape(cell));
+    }
+    table.push_str("</tr>");
+    for row in rows {
+        table.push_str("<tr>");
+        for cell in row {
+            let _ = write!(table, "<td>{}</td>", escape(cell));
+        }
+        table.push_str("</tr>");
+    }
+    table.push_str("</table>");
+    table
+}
+
+/// Returns whole report as an HTML document
+fn render(report: &Report) -> anyhow::Result<String> {
+    let target = redact_url(
+        report
+            .config
+            .get("url")
+            .and_then(Value::as_str)
+            .unwrap_or_default(),
+    );
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>stresster report</title>\
+         <style>{}</style></head><body><h1>stresster report</h1>",
+        STYLE
+    );
+
+    // Totals of the run
+    let summary = vec![
+        vec!["Target".to_string(), target],
+        vec![
+            "Started at".to_string(),
+            report
+                .started_at
+                .format("%Y-%m-%d %H:%M:%S UTC")
+                .to_string(),
+        ],
+        vec![
+            "Duration".to_string(),
+            format!("{:.3}s", report.duration.as_secs_f64()),
+        ],
+        vec!["Requests".to_string(), report.completed().to_string()],
+        vec![
+            "Requests per second".to_string(),
+            format!("{:.2}", report.per_second(report.completed())),
+        ],
+        vec![
+            "Error rate".to_string(),
+            format!("{:.2}%", report.error_rate() * 100.0),
+        ],
+    ];
+    html.push_str("<h2>Summary</h2>");
+    html.push_str(&html_table(&["", ""], &summary));
+
+    // Charts built from results per interval
+    let latency_series = |label: &'static str, color| Series {
+        label,
+        color,
+        points: report
+            .timeseries
+            .iter()
+            .filter(|interval| interval.requests > 0)
+            .filter_map(|interval| {
+                interval
+                    .latency_summary()
+                    .into_iter()
+                    .find(|(name, _)| *name == label)
+                    .map(|(_, value)| (interval.start.as_secs_f64(), value))
+            })
+            .collect(),
+    };
+    html.push_str("<h2>Latency over time</h2>");
+    html.push_str(&line_chart(
+        "Latency (ms)",
+        &[
+            latency_series("p50", "#1f77b4"),
+            latency_series("p90", "#ff7f0e"),
+            latency_series("p99", "#d62728"),
+        ],
+    ));
+    html.push_str("<h2>Requests per second</h2>");
+    html.push_str(&line_chart(
+        "Requests per second",
+        &[Series {
+            label: "rps",
+            color: "#2ca02c",
+            points: report
+                .timeseries
+                .iter()
+                .map(|interval| (interval.start.as_secs_f64(), rps(report, interval)))
+                .collect(),
+        }],
+    ));
+
+    // Latency distribution in total and per phase
+    let mut header = vec!["Latency (ms)", "count"];
+    header.extend(report.latency.summary().into_iter().map(|(label, _)| label));
+    let mut latencies = vec![("total", &report.latency)];
+    if report.corrected_latency.count() > 0 {
+        latencies.push(("corrected", &report.corrected_latency));
+    }
+    latencies.extend(report.phases.all());
+    let rows = latencies
+        .into_iter()
+        .map(|(name, latency)| {
+            let mut row = vec![name.to_string(), latency.count().to_string()];
+            row.extend(
+                latency
+                    .summary()
+                    .into_iter()
+                    .map(|(_, value)| format!("{:.3}", value)),
+            );
+            row
+        })
+        .collect::<Vec<_>>();
+    html.push_str("<h2>Percentiles</h2>");
+    html.push_str(&html_table(&header, &rows));
+
+    let mut status_codes = report.status_codes.iter().collect::<Vec<_>>();
+    status_codes.sort();
+    let rows = status_codes
+        .into_iter()
+        .map(|(code, count)| vec![code.to_string(), count.to_string()])
+        .collect::<Vec<_>>();
+    html.push_str("<h2>Status codes</h2>");
+    html.push_str(&html_table(&["Status Code", "Count"], &rows));
+
+    // Errors are shown only if any request failed
+    if !report.errors.is_empty() {
+        let mut errors = report.errors.iter().collect::<Vec<_>>();
+        errors.sort();
+        let rows = errors
+            .into_iter()
+            .map(|(kind, count)| vec![kind.clone(), count.to_string()])
+            .collect::<Vec<_>>();
+        html.push_str("<h2>Errors</h2>");
+        html.push_str(&html_table(&["Error", "Count"], &rows));
+    }
+
+    let config = serde_json::to_string_pretty(&redact_config(&report.config))
+        .context("Failed to prettify config")?;
+    let _ = write!(
+        html,
+        "<h2>Configuration</h2><pre>{}</pre></body></html>",
+        escape(&config)
+    );
+    Ok(html)
+}
+
+#[async_trait()]
+impl output_producer::OutputProducer for HTMLProducer {
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()> {
+        let logger = logger.clone();
+        debug!(logger, "Writing output in HTML format");
+        let report = report.lock().await;
+        writeln!(out, "{}", render(&report)?)?;
+        Ok(())
+    }
+
+    async fn format_name(&self) -> String {
+        "Html".to_string()
+    }
+}
@@ -1,23 +1,38 @@
 use anyhow::Context;
 use async_trait::async_trait;
+use chrono::SecondsFormat;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::io::Write;
 
 use crate::output_producers::output_producer;
-use crate::types::{Countermap, Logger};
+use crate::report::{Latency, Report};
+use crate::timeseries::timeseries_json;
+use crate::types::{Logger, SharedReport};
+
+/// Version of JSON output schema, incremented on every incompatible change
+pub const JSON_SCHEMA_VERSION: u64 = 1;
 
 /// Struct that produces JSON output
 pub struct JSONProducer;
 
 #[async_trait()]
 impl output_producer::OutputProducer for JSONProducer {
-    async fn produce(&self, counter_map: Countermap, logger: Logger) -> anyhow::Result<()> {
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()> {
         let logger = logger.clone();
         debug!(logger, "Writing output in JSON format");
-        let map = counter_map.lock().await;
+        let report = report.lock().await;
+        let output = report_json(&report);
 
         // Create nice JSON using serde
-        let serialized_json = serde_json::to_string_pretty(&*map)
+        let serialized_json = serde_json::to_string_pretty(&output)
             .with_context(|| "Failed to prettify JSON output".to_string())?;
-        println!("{}", serialized_json);
+        writeln!(out, "{}", serialized_json)?;
         Ok(())
     }
 
@@ -25,3 +40,107 @@ impl output_producer::OutputProducer for JSONProducer {
         "Json".to_string()
     }
 }
+
+/// Returns whole report as a JSON object as per schema documented in README
+pub fn report_json(report: &Report) -> Value {
+    let finished_at = report.started_at
+        + chrono::Duration::from_std(report.duration).unwrap_or_else(|_| chrono::Duration::zero());
+    // Config is hashed in canonical form, as keys of serialized objects are sorted
+    let config_hash = Sha256::digest(serde_json::to_vec(&report.config).unwrap_or_default())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    let config_field = |name: &str| report.config.get(name).cloned().unwrap_or(Value::Null);
+
+    json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "metadata": {
+            "stresster_version": env!("CARGO_PKG_VERSION"),
+            "started_at": report.started_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "finished_at": finished_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "duration": report.duration.as_secs_f64(),
+            "config_hash": format!("sha256:{}", config_hash),
+            "target": {
+                "url": config_field("url"),
+                "method": config_field("method"),
+            },
+        },
+        "totals": {
+            "requests": report.completed(),
+            "failed": report.failed(),
+            "error_rate": report.error_rate(),
+            "rps": report.per_second(report.completed()),
+            "redirects": {
+                "total": report.total_redirects(),
+                "status_codes": report.redirect_codes,
+            },
+            "bytes": {
+                "sent": {
+                    "total": report.bytes_sent,
+                    "per_second": report.per_second(report.bytes_sent),
+                },
+                "received": {
+                    "total": report.bytes_received,
+                    "per_second": report.per_second(report.bytes_received),
+                },
+                "decompressed": {
+                    "total": report.bytes_decoded,
+                    "per_second": report.per_second(report.bytes_decoded),
+                },
+            },
+        },
+        "status_codes": report.status_codes,
+        "latency": latency_summary(&report.latency),
+        "corrected_latency": (report.corrected_latency.count() > 0)
+            .then(|| latency_summary(&report.corrected_latency)),
+        "phases": report
+            .phases
+            .all()
+            .into_iter()
+            .map(|(name, latency)| (name.to_string(), latency_summary(latency)))
+            .collect::<Map<String, Value>>(),
+        "errors": report.errors,
+        "endpoints": report
+            .endpoints
+            .iter()
+            .map(|(name, endpoint)| {
+                (
+                    name.clone(),
+                    json!({
+                        "requests": endpoint.requests,
+                        "failed": endpoint.errors,
+                        "error_rate": endpoint.error_rate(),
+                        "rps": report.per_second(endpoint.requests),
+                        "status_codes": endpoint.status_codes,
+                        "latency": latency_summary(&endpoint.latency),
+                    }),
+                )
+            })
+            .collect::<Map<String, Value>>(),
+        "thresholds": report
+            .thresholds
+            .iter()
+            .map(|result| {
+                json!({
+                    "metric": result.threshold.metric,
+                    "limit": result.threshold.limit,
+                    "observed": result.observed,
+                    "passed": result.passed,
+                })
+            })
+            .collect::<Vec<Value>>(),
+        "interval": report.interval.as_secs_f64(),
+        "timeseries": timeseries_json(report),
+    })
+}
+
+/// Returns latency statistics in milliseconds as a JSON object
+fn latency_summary(latency: &Latency) -> Value {
+    let mut summary = latency
+        .summary()
+        .into_iter()
+        .map(|(label, value)| (label.to_string(), json!(value)))
+        .collect::<Map<String, Value>>();
+    summary.insert("count".to_string(), json!(latency.count()));
+    summary.into()
+}
@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::output_producers::output_producer;
+use crate::redact::redact_url;
+use crate::report::Report;
+use crate::types::{Logger, SharedReport};
+
+/// Struct that produces JUnit XML output. Every threshold becomes a testcase which fails if
+/// results of the run exceed its limit.
+pub struct JUnitProducer;
+
+/// Escapes text to be placed inside XML attributes and elements
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Returns whole report as a JUnit XML document
+fn render(report: &Report) -> String {
+    let target = redact_url(
+        report
+            .config
+            .get("url")
+            .and_then(|url| url.as_str())
+            .unwrap_or_default(),
+    );
+    let failures = report
+        .thresholds
+        .iter()
+        .filter(|result| !result.passed)
+        .count();
+    let time = report.duration.as_secs_f64();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"stresster\" tests=\"{tests}\" failures=\"{failures}\" time=\"{time:.3}\">\n  \
+         <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" \
+         time=\"{time:.3}\" timestamp=\"{timestamp}\">",
+        tests = report.thresholds.len(),
+        failures = failures,
+        time = time,
+        name = escape(&target),
+        timestamp = report.started_at.format("%Y-%m-%dT%H:%M:%S"),
+    );
+    for result in &report.thresholds {
+        let threshold = &result.threshold;
+        let _ = write!(
+            xml,
+            "    <testcase classname=\"stresster.thresholds\" name=\"{}\" time=\"{:.3}\"",
+            escape(&threshold.describe()),
+            time
+        );
+        if result.passed {
+            xml.push_str("/>\n");
+            continue;
+        }
+
+        let bound = if threshold.is_minimum() {
+            "at least"
+        } else {
+            "at most"
+        };
+        let message = format!(
+            "{} was {:.3}{}, expected {} {}{}",
+            threshold.metric,
+            result.observed,
+            threshold.unit(),
+            bound,
+            threshold.limit,
+            threshold.unit()
+        );
+        let _ = writeln!(
+            xml,
+            ">\n      <failure message=\"{message}\" type=\"threshold\">{message}</failure>\n    </testcase>",
+            message = escape(&message)
+        );
+    }
+    xml.push_str("  </testsuite>\n</testsuites>");
+    xml
+}
+
+#[async_trait()]
+impl output_producer::OutputProducer for JUnitProducer {
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()> {
+        let logger = logger.clone();
+        debug!(logger, "Writing output in JUnit format");
+        let report = report.lock().await;
+        writeln!(out, "{}", render(&report))?;
+        Ok(())
+    }
+
+    async fn format_name(&self) -> String {
+        "Junit".to_string()
+    }
+}
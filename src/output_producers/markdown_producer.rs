@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::output_producers::output_producer;
+use crate::redact::redact_url;
+use crate::report::Report;
+use crate::types::{Logger, SharedReport};
+
+/// Struct that produces a compact GitHub flavored Markdown summary
+pub struct MarkdownProducer;
+
+/// Returns whole report as a Markdown document
+fn render(report: &Report) -> String {
+    let target = redact_url(
+        report
+            .config
+            .get("url")
+            .and_then(|url| url.as_str())
+            .unwrap_or_default(),
+    );
+    let mut markdown = String::new();
+    let _ = writeln!(markdown, "### stresster results for `{}`\n", target);
+
+    // Totals and latency in a single row
+    let latency = report.latency.summary();
+    let mut header = vec!["Requests", "RPS", "Error rate"];
+    header.extend(latency.iter().map(|(label, _)| *label));
+    let mut row = vec![
+        report.completed().to_string(),
+        format!("{:.2}", report.per_second(report.completed())),
+        format!("{:.2}%", report.error_rate() * 100.0),
+    ];
+    row.extend(latency.iter().map(|(_, value)| format!("{:.2} ms", value)));
+    let _ = writeln!(markdown, "| {} |", header.join(" | "));
+    let _ = writeln!(markdown, "|{}", "---:|".repeat(header.len()));
+    let _ = writeln!(markdown, "| {} |\n", row.join(" | "));
+
+    // Corrected latency is there only if requests were sent at a fixed rate
+    if report.corrected_latency.count() > 0 {
+        let corrected = report
+            .corrected_latency
+            .summary()
+            .into_iter()
+            .map(|(label, value)| format!("{} {:.2} ms", label, value))
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            markdown,
+            "**Corrected latency:** {}\n",
+            corrected.join(", ")
+        );
+    }
+
+    let mut status_codes = report.status_codes.iter().collect::<Vec<_>>();
+    status_codes.sort();
+    let status_codes = status_codes
+        .into_iter()
+        .map(|(code, count)| format!("`{}` × {}", code, count))
+        .collect::<Vec<_>>();
+    let _ = writeln!(markdown, "**Status codes:** {}\n", status_codes.join(", "));
+
+    // Thresholds are shown only if any was configured
+    if !report.thresholds.is_empty() {
+        markdown.push_str("| Threshold | Observed | Result |\n|---|---:|:---:|\n");
+        for result in &report.thresholds {
+            let _ = writeln!(
+                markdown,
+                "| `{}` | {:.3}{} | {} |",
+                result.threshold.describe(),
+                result.observed,
+                result.threshold.unit(),
+                if result.passed {
+                    "✅ pass"
+                } else {
+                    "❌ fail"
+                }
+            );
+        }
+        markdown.push('\n');
+    }
+
+    let _ = write!(
+        markdown,
+        "<sub>Duration {:.3}s, started at {}</sub>",
+        report.duration.as_secs_f64(),
+        report.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    markdown
+}
+
+#[async_trait()]
+impl output_producer::OutputProducer for MarkdownProducer {
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()> {
+        let logger = logger.clone();
+        debug!(logger, "Writing output in Markdown format");
+        let report = report.lock().await;
+        writeln!(out, "{}", render(&report))?;
+        Ok(())
+    }
+
+    async fn format_name(&self) -> String {
+        "Markdown".to_string()
+    }
+}
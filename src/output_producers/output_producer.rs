@@ -1,11 +1,17 @@
 use async_trait::async_trait;
+use std::io::Write;
 
-use crate::types::{Countermap, Logger};
+use crate::types::{Logger, SharedReport};
 
 #[async_trait()]
 pub trait OutputProducer {
-    /// Contains a logic to render the output.
-    async fn produce(&self, counter_map: Countermap, logger: Logger) -> anyhow::Result<()>;
+    /// Contains a logic to render the output to supplied writer.
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()>;
 
     /// Returns the name of the format the concrete producer type is going to produce.
     async fn format_name(&self) -> String;
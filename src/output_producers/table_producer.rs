@@ -1,6 +1,7 @@
-use crate::types::{Countermap, Logger};
+use crate::types::{Logger, SharedReport};
 use async_trait::async_trait;
 use prettytable::{Cell, Row, Table};
+use std::io::Write;
 
 use super::output_producer::OutputProducer;
 
@@ -8,21 +9,78 @@ pub struct TableProducer;
 
 #[async_trait()]
 impl OutputProducer for TableProducer {
-    async fn produce(&self, counter_map: Countermap, logger: Logger) -> anyhow::Result<()> {
+    async fn produce(
+        &self,
+        report: SharedReport,
+        logger: Logger,
+        out: &mut (dyn Write + Send),
+    ) -> anyhow::Result<()> {
         let logger = logger.clone();
         debug!(logger, "Writing output in tabular format");
         // Create nice tabular view to make output easily understandable
         let mut table = Table::new();
         table.add_row(row!["Status Code", "Count"]);
 
-        let map = counter_map.lock().await;
-        for key in &*map {
+        let report = report.lock().await;
+        for key in &report.status_codes {
             table.add_row(Row::new(vec![
                 Cell::new(&key.0.to_string()),
                 Cell::new(&key.1.to_string()),
             ]));
         }
-        table.printstd();
+        table.print(out)?;
+
+        // Redirect hops are shown only if any redirect was followed
+        if !report.redirect_codes.is_empty() {
+            let mut table = Table::new();
+            table.add_row(row!["Redirect Code", "Hops"]);
+            for key in &report.redirect_codes {
+                table.add_row(Row::new(vec![
+                    Cell::new(&key.0.to_string()),
+                    Cell::new(&key.1.to_string()),
+                ]));
+            }
+            table.add_row(row!["Total", report.total_redirects()]);
+            table.print(out)?;
+        }
+
+        // Throughput of request and response bodies
+        let mut table = Table::new();
+        table.add_row(row!["Bytes", "Total", "Per Second"]);
+        for (name, total) in &[
+            ("Sent", report.bytes_sent),
+            ("Received", report.bytes_received),
+            ("Decompressed", report.bytes_decoded),
+        ] {
+            table.add_row(row![
+                name,
+                total,
+                format!("{:.2}", report.per_second(*total))
+            ]);
+        }
+        table.print(out)?;
+
+        // Latency distribution in milliseconds, in total and per phase
+        let mut table = Table::new();
+        let mut header = vec![Cell::new("Latency (ms)"), Cell::new("count")];
+        for (label, _) in report.latency.summary() {
+            header.push(Cell::new(label));
+        }
+        table.add_row(Row::new(header));
+        let mut latencies = vec![("total", &report.latency)];
+        if report.corrected_latency.count() > 0 {
+            latencies.push(("corrected", &report.corrected_latency));
+        }
+        latencies.extend(report.phases.all());
+        for (name, latency) in latencies {
+            let mut row = vec![Cell::new(name), Cell::new(&latency.count().to_string())];
+            for (_, value) in latency.summary() {
+                row.push(Cell::new(&format!("{:.3}", value)));
+            }
+            table.add_row(Row::new(row));
+        }
+        table.print(out)?;
+        writeln!(out, "Duration: {:.3}s", report.duration.as_secs_f64())?;
         Ok(())
     }
 
@@ -0,0 +1,76 @@
+use crate::types::SharedReport;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Shows live progress of the run on stderr every second till the task is aborted. Progress is
+/// redrawn in place on a terminal and printed as a line otherwise.
+pub async fn show_progress(report: SharedReport, sent: Arc<AtomicU64>, start: Instant) {
+    let is_terminal = io::stderr().is_terminal();
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut last_tick = Instant::now();
+    let mut last_completed = 0;
+    let mut lines = 0;
+
+    // First tick completes immediately
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let (completed, window, mut status_codes) = {
+            let mut report = report.lock().await;
+            let window = std::mem::take(&mut report.window);
+            let status_codes = report
+                .status_codes
+                .iter()
+                .map(|(code, count)| (*code, *count));
+            (
+                report.completed(),
+                window,
+                status_codes.collect::<Vec<(u16, i32)>>(),
+            )
+        };
+        let sent = sent.load(Ordering::SeqCst);
+        let rps = (completed - last_completed) as f64 / last_tick.elapsed().as_secs_f64();
+        last_tick = Instant::now();
+        last_completed = completed;
+
+        status_codes.sort_unstable();
+        let status_codes = status_codes
+            .iter()
+            .map(|(code, count)| format!("{}: {}", code, count))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let frame = [
+            format!(
+                "Elapsed: {}s  Sent: {}  Completed: {}  In-flight: {}  RPS: {:.1}",
+                start.elapsed().as_secs(),
+                sent,
+                completed,
+                sent.saturating_sub(completed),
+                rps
+            ),
+            format!(
+                "Latency (last second): p50 {:.3}ms  p99 {:.3}ms",
+                window.percentile(50.0),
+                window.percentile(99.0)
+            ),
+            format!("Status codes: {}", status_codes),
+        ];
+
+        // Progress is best effort, failing to show it must not stop the run
+        let mut stderr = io::stderr().lock();
+        if is_terminal {
+            if lines > 0 {
+                let _ = write!(stderr, "\x1b[{}A", lines);
+            }
+            for line in &frame {
+                let _ = writeln!(stderr, "\x1b[2K{}", line);
+            }
+            lines = frame.len();
+        } else {
+            let _ = writeln!(stderr, "{}", frame.join(" | "));
+        }
+    }
+}
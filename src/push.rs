@@ -0,0 +1,319 @@
+use crate::report::{Interval, Report};
+use crate::timeseries::rps;
+use crate::timing::backend_client;
+use crate::types::{Logger, Options, SharedReport};
+use anyhow::{anyhow, Context};
+use reqwest::{Client, Url};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+
+/// Maximum time a single push over HTTP takes
+const PUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum time the run waits for intervals not pushed yet once it's finished
+pub const FINAL_PUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Destination results per interval are pushed to
+enum Sink {
+    /// InfluxDB line protocol over UDP
+    InfluxUdp(UdpSocket),
+
+    /// InfluxDB line protocol over HTTP write API
+    InfluxHttp(Client, Url),
+
+    /// StatsD over UDP, with tags if DogStatsD is used
+    Statsd { socket: UdpSocket, tags: bool },
+}
+
+/// Pushes results of every interval to metrics backends once the interval is over
+pub struct Pusher {
+    sinks: Vec<Sink>,
+
+    /// Tag identifying the run, URL of the target
+    target: String,
+
+    /// Number of intervals pushed so far per sink
+    pushed: Vec<usize>,
+
+    logger: Logger,
+}
+
+pub type SharedPusher = Arc<Mutex<Pusher>>;
+
+/// Returns a UDP socket connected to supplied address
+async fn connect_udp(address: &str) -> anyhow::Result<UdpSocket> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .context("Failed to bind UDP socket")?;
+    socket
+        .connect(address)
+        .await
+        .with_context(|| format!("Failed to connect UDP socket to {}", address))?;
+    Ok(socket)
+}
+
+/// Replaces characters not allowed in DogStatsD tags, e.g. `|`, `,` and `#`, with underscores
+fn escape_dogstatsd_tag(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_-:./".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Escapes commas, spaces and equal signs in InfluxDB tag values
+fn escape_tag(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+impl Pusher {
+    /// Returns a pusher for backends supplied in options, `None` if no backend is supplied
+    pub async fn new(
+        options: &Options,
+        target: &str,
+        logger: Logger,
+    ) -> anyhow::Result<Option<Pusher>> {
+        let mut sinks = vec![];
+        if let Some(influx) = &options.influx {
+            let url =
+                Url::parse(influx).with_context(|| format!("Invalid InfluxDB URL {}", influx))?;
+            let sink = match url.scheme() {
+                "udp" => {
+                    let host = url
+                        .host_str()
+                        .ok_or_else(|| anyhow!("Missing host in InfluxDB URL {}", influx))?;
+                    let port = url
+                        .port()
+                        .ok_or_else(|| anyhow!("Missing port in InfluxDB URL {}", influx))?;
+                    Sink::InfluxUdp(connect_udp(&format!("{}:{}", host, port)).await?)
+                }
+                "http" | "https" => Sink::InfluxHttp(
+                    backend_client()
+                        .timeout(PUSH_TIMEOUT)
+                        .build()
+                        .context("Failed to build InfluxDB client")?,
+                    url,
+                ),
+                scheme => {
+                    return Err(anyhow!(
+                        "Unsupported InfluxDB URL scheme {}, use udp, http or https",
+                        scheme
+                    ))
+                }
+            };
+            sinks.push(sink);
+        }
+        if let Some(statsd) = &options.statsd {
+            sinks.push(Sink::Statsd {
+                socket: connect_udp(statsd).await?,
+                tags: options.dogstatsd,
+            });
+        }
+
+        if sinks.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Pusher {
+            pushed: vec![0; sinks.len()],
+            sinks,
+            target: target.to_owned(),
+            logger,
+        }))
+    }
+
+    /// Pushes intervals that are over by at least an interval, so late results are included
+    pub async fn push_finished(&mut self, report: &SharedReport, elapsed: Duration) {
+        let lines = {
+            let report = report.lock().await;
+            let finished = report
+                .timeseries
+                .iter()
+                .take_while(|interval| interval.start + report.interval * 2 <= elapsed)
+                .count();
+            self.pending_lines(&report, finished)
+        };
+        self.send(lines).await;
+    }
+
+    /// Pushes all the intervals not pushed yet, used once the run is finished
+    pub async fn push_remaining(&mut self, report: &SharedReport) {
+        let lines = {
+            let report = report.lock().await;
+            self.pending_lines(&report, report.timeseries.len())
+        };
+        self.send(lines).await;
+    }
+
+    /// Returns lines per sink and interval for intervals not pushed to the sink yet till
+    /// supplied number of intervals
+    fn pending_lines(&self, report: &Report, till: usize) -> Vec<Vec<Vec<String>>> {
+        self.sinks
+            .iter()
+            .zip(&self.pushed)
+            .map(|(sink, pushed)| {
+                report
+                    .timeseries
+                    .get(*pushed..till)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|interval| match sink {
+                        Sink::InfluxUdp(_) | Sink::InfluxHttp(..) => {
+                            self.influx_lines(report, interval)
+                        }
+                        Sink::Statsd { tags, .. } => self.statsd_lines(report, interval, *tags),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns results of an interval in InfluxDB line protocol
+    fn influx_lines(&self, report: &Report, interval: &Interval) -> Vec<String> {
+        let timestamp = (report.started_at
+            + chrono::Duration::from_std(interval.start)
+                .unwrap_or_else(|_| chrono::Duration::zero()))
+        .timestamp_nanos_opt()
+        .unwrap_or_default();
+        let target = escape_tag(&self.target);
+
+        let mut fields = vec![
+            format!("requests={}i", interval.requests),
+            format!("errors={}i", interval.errors),
+            format!("error_rate={}", interval.error_rate()),
+            format!("rps={}", rps(report, interval)),
+        ];
+        if interval.latencies > 0 {
+            fields.extend(
+                interval
+                    .latency_summary()
+                    .into_iter()
+                    .map(|(label, value)| format!("latency_{}_ms={}", label, value)),
+            );
+        }
+        let mut lines = vec![format!(
+            "stresster,target={} {} {}",
+            target,
+            fields.join(","),
+            timestamp
+        )];
+
+        let mut status_codes = interval.status_codes.iter().collect::<Vec<_>>();
+        status_codes.sort();
+        lines.extend(status_codes.into_iter().map(|(code, count)| {
+            format!(
+                "stresster_status,target={},status={} count={}i {}",
+                target, code, count, timestamp
+            )
+        }));
+        lines
+    }
+
+    /// Returns results of an interval as StatsD counters and gauges. Latency statistics are
+    /// gauges in milliseconds as they're already aggregated.
+    fn statsd_lines(&self, report: &Report, interval: &Interval, tags: bool) -> Vec<String> {
+        let target_tag = if tags {
+            format!("|#target:{}", escape_dogstatsd_tag(&self.target))
+        } else {
+            String::new()
+        };
+        let mut lines = vec![
+            format!("stresster.requests:{}|c{}", interval.requests, target_tag),
+            format!("stresster.errors:{}|c{}", interval.errors, target_tag),
+            format!(
+                "stresster.error_rate:{}|g{}",
+                interval.error_rate(),
+                target_tag
+            ),
+            format!("stresster.rps:{}|g{}", rps(report, interval), target_tag),
+        ];
+        if interval.latencies > 0 {
+            lines.extend(
+                interval
+                    .latency_summary()
+                    .into_iter()
+                    .map(|(label, value)| {
+                        format!("stresster.latency.{}:{}|g{}", label, value, target_tag)
+                    }),
+            );
+        }
+
+        let mut status_codes = interval.status_codes.iter().collect::<Vec<_>>();
+        status_codes.sort();
+        lines.extend(status_codes.into_iter().map(|(code, count)| {
+            if tags {
+                format!(
+                    "stresster.responses:{}|c{},status:{}",
+                    count, target_tag, code
+                )
+            } else {
+                format!("stresster.responses.{}:{}|c", code, count)
+            }
+        }));
+        lines
+    }
+
+    /// Sends lines of every interval to their sinks. An interval is accounted as pushed to a
+    /// sink only once it's sent, so intervals that failed are sent again on the next push.
+    /// Failures are logged and don't stop the run.
+    async fn send(&mut self, lines: Vec<Vec<Vec<String>>>) {
+        for ((sink, pushed), intervals) in self.sinks.iter().zip(&mut self.pushed).zip(lines) {
+            for lines in intervals {
+                if let Err(e) = Self::send_lines(sink, &lines).await {
+                    error!(self.logger, "Failed to push metrics : {}", e);
+                    break;
+                }
+                *pushed += 1;
+            }
+        }
+    }
+
+    /// Sends lines of a single interval to a sink
+    async fn send_lines(sink: &Sink, lines: &[String]) -> anyhow::Result<()> {
+        match sink {
+            // Every line is sent in its own datagram to stay within packet size
+            Sink::InfluxUdp(socket) | Sink::Statsd { socket, .. } => {
+                for line in lines {
+                    socket.send(line.as_bytes()).await?;
+                }
+                Ok(())
+            }
+            Sink::InfluxHttp(client, url) => {
+                client
+                    .post(url.clone())
+                    .body(lines.join("\n"))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Pushes results of every finished interval till the task is aborted
+pub async fn push_metrics(
+    pusher: SharedPusher,
+    report: SharedReport,
+    period: Duration,
+    start: Instant,
+) {
+    let mut ticker = tokio::time::interval(period);
+    loop {
+        ticker.tick().await;
+        pusher
+            .lock()
+            .await
+            .push_finished(&report, start.elapsed())
+            .await;
+    }
+}
@@ -0,0 +1,70 @@
+use crate::types::RequestResult;
+use anyhow::Context;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Size of buffer rows are collected in before they're written to the file
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Writes result of every request to a CSV file as soon as it's accounted, so results of long
+/// runs aren't kept in memory
+pub struct RawLog {
+    path: PathBuf,
+    writer: csv::Writer<File>,
+
+    /// Time the run started at, timestamps of requests are counted from
+    started_at: DateTime<Utc>,
+}
+
+impl RawLog {
+    /// Creates the raw log file and writes its header
+    pub fn create(path: &Path, started_at: DateTime<Utc>) -> anyhow::Result<RawLog> {
+        let mut writer = csv::WriterBuilder::new()
+            .buffer_capacity(BUFFER_SIZE)
+            .from_path(path)
+            .with_context(|| format!("Failed to create CSV file {}", path.display()))?;
+        writer
+            .write_record([
+                "timestamp",
+                "endpoint",
+                "status",
+                "latency_ms",
+                "ttfb_ms",
+                "bytes_sent",
+                "bytes_received",
+                "error",
+            ])
+            .with_context(|| format!("Failed to write CSV file {}", path.display()))?;
+        Ok(RawLog {
+            path: path.to_owned(),
+            writer,
+            started_at,
+        })
+    }
+
+    /// Writes a row with result of a single request
+    pub fn write(&mut self, result: &RequestResult) -> anyhow::Result<()> {
+        let timestamp = self.started_at
+            + Duration::from_std(result.completed_at).unwrap_or_else(|_| Duration::zero());
+        self.writer
+            .write_record([
+                timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+                result.endpoint.clone(),
+                result.status.to_string(),
+                format!("{:.3}", result.latency.as_secs_f64() * 1000.0),
+                format!("{:.3}", result.ttfb.as_secs_f64() * 1000.0),
+                result.bytes_sent.to_string(),
+                result.bytes_received.to_string(),
+                result.error.clone().unwrap_or_default(),
+            ])
+            .with_context(|| format!("Failed to write CSV file {}", self.path.display()))
+    }
+
+    /// Writes rows left in the buffer
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.writer
+            .flush()
+            .with_context(|| format!("Failed to write CSV file {}", self.path.display()))
+    }
+}
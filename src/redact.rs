@@ -0,0 +1,67 @@
+use reqwest::Url;
+use serde_json::Value;
+
+/// Placeholder written instead of secrets
+const MASK: &str = "***";
+
+/// Returns true if a field or header of this name carries a secret, e.g. `password`,
+/// `Authorization`, `Cookie` or `X-Api-Key`
+pub fn is_secret(name: &str) -> bool {
+    let name = name.to_lowercase();
+    matches!(
+        name.as_str(),
+        "password" | "authorization" | "proxy-authorization" | "cookie" | "set-cookie"
+    ) || ["api-key", "api_key", "apikey", "token", "secret"]
+        .iter()
+        .any(|part| name.contains(part))
+}
+
+/// Returns a URL with credentials in it masked, e.g. `socks5://***@localhost:1080`. Other text
+/// is returned as it is.
+pub fn redact_url(text: &str) -> String {
+    match Url::parse(text) {
+        Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+            let _ = url.set_username(MASK);
+            let _ = url.set_password(None);
+            url.to_string()
+        }
+        _ => text.to_owned(),
+    }
+}
+
+/// Returns a copy of config with secrets masked, so it can be shared or stored
+pub fn redact_config(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| {
+                    if is_secret(key) {
+                        (key.clone(), Value::String(MASK.to_string()))
+                    } else {
+                        (key.clone(), redact_config(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.iter().map(redact_config).collect()),
+        Value::String(text) => Value::String(redact_url(text)),
+        value => value.clone(),
+    }
+}
+
+/// Returns a copy of a JSON value with credentials in URLs masked, in keys too as endpoints are
+/// named after their URL by default
+pub fn redact_urls(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (redact_url(key), redact_urls(value)))
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.iter().map(redact_urls).collect()),
+        Value::String(text) => Value::String(redact_url(text)),
+        value => value.clone(),
+    }
+}
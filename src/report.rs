@@ -0,0 +1,416 @@
+use crate::thresholds::ThresholdResult;
+use crate::types::{Map, RequestResult};
+use chrono::{DateTime, Utc};
+use hdrhistogram::Histogram;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Percentiles shown in the output along with their labels
+pub const PERCENTILES: [(&str, f64); 4] =
+    [("p50", 50.0), ("p90", 90.0), ("p95", 95.0), ("p99", 99.0)];
+
+/// Durations longer than an hour are recorded as an hour
+const MAX_LATENCY_MICROS: u64 = 3_600_000_000;
+
+/// Distribution of durations, values are reported in milliseconds
+#[derive(Debug, Clone)]
+pub struct Latency {
+    /// Durations in microseconds
+    histogram: Histogram<u64>,
+}
+
+impl Default for Latency {
+    fn default() -> Latency {
+        Latency {
+            histogram: Histogram::new_with_bounds(1, MAX_LATENCY_MICROS, 3).unwrap(),
+        }
+    }
+}
+
+impl Latency {
+    /// Adds a duration to the distribution
+    pub fn record(&mut self, duration: Duration) {
+        let micros = duration.as_micros().min(MAX_LATENCY_MICROS as u128) as u64;
+        self.histogram.saturating_record(micros);
+    }
+
+    /// Returns number of recorded durations
+    pub fn count(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    /// Returns number of recorded durations up to supplied one, at the precision of the
+    /// distribution
+    pub fn count_up_to(&self, duration: Duration) -> u64 {
+        let micros = duration.as_micros().min(MAX_LATENCY_MICROS as u128) as u64;
+        self.histogram.count_between(0, micros)
+    }
+
+    /// Returns sum of recorded durations in milliseconds, at the precision of the distribution
+    pub fn sum(&self) -> f64 {
+        self.mean() * self.count() as f64
+    }
+
+    pub fn min(&self) -> f64 {
+        if self.histogram.is_empty() {
+            return 0.0;
+        }
+        self.histogram.min() as f64 / 1000.0
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.histogram.mean() / 1000.0
+    }
+
+    pub fn max(&self) -> f64 {
+        self.histogram.max() as f64 / 1000.0
+    }
+
+    /// Returns duration below which supplied percentage of durations fall
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        self.histogram.value_at_percentile(percentile) as f64 / 1000.0
+    }
+
+    /// Returns summary statistics along with their labels
+    pub fn summary(&self) -> Vec<(&'static str, f64)> {
+        let mut summary = vec![("min", self.min()), ("mean", self.mean())];
+        for (label, percentile) in &PERCENTILES {
+            summary.push((label, self.percentile(*percentile)));
+        }
+        summary.push(("max", self.max()));
+        summary
+    }
+}
+
+/// Number of most recent intervals that keep whole latency distribution. Older intervals keep
+/// only summary statistics to bound memory of long runs.
+const OPEN_INTERVALS: usize = 2;
+
+/// Results of requests completed during a single interval of the run
+#[derive(Debug, Clone)]
+pub struct Interval {
+    /// Time since start of the run when the interval starts
+    pub start: Duration,
+
+    /// Number of completed requests
+    pub requests: u64,
+
+    /// Number of failed requests
+    pub errors: u64,
+
+    /// Number of requests per final status code
+    pub status_codes: Map,
+
+    /// Number of latencies recorded, i.e. of requests that got a response
+    pub latencies: u64,
+
+    /// Latency distribution, dropped once interval is closed
+    latency: Option<Latency>,
+
+    /// Latency statistics, calculated once interval is closed
+    summary: Vec<(&'static str, f64)>,
+}
+
+impl Interval {
+    fn new(start: Duration) -> Interval {
+        Interval {
+            start,
+            requests: 0,
+            errors: 0,
+            status_codes: Map::new(),
+            latencies: 0,
+            latency: Some(Latency::default()),
+            summary: vec![],
+        }
+    }
+
+    /// Accounts the result of a single request
+    pub fn record(&mut self, result: &RequestResult) {
+        self.requests += 1;
+        if result.is_failure() {
+            self.errors += 1;
+        }
+        *self.status_codes.entry(result.status).or_insert(0) += 1;
+        if let (Some(latency), true) = (self.latency.as_mut(), result.status != 0) {
+            latency.record(result.latency);
+            self.latencies += 1;
+        }
+    }
+
+    /// Keeps only summary statistics of latency distribution
+    fn close(&mut self) {
+        if let Some(latency) = self.latency.take() {
+            self.summary = latency.summary();
+        }
+    }
+
+    /// Returns latency statistics in milliseconds along with their labels
+    pub fn latency_summary(&self) -> Vec<(&'static str, f64)> {
+        match &self.latency {
+            Some(latency) => latency.summary(),
+            None => self.summary.clone(),
+        }
+    }
+
+    /// Returns ratio of failed requests to all the requests
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Distributions of time taken by each phase of requests
+#[derive(Debug, Clone, Default)]
+pub struct Phases {
+    /// DNS lookup, only for requests that opened a new connection
+    pub dns: Latency,
+
+    /// TCP connect, only for requests that opened a new connection
+    pub connect: Latency,
+
+    /// TLS handshake, only for requests that opened a new HTTPS connection
+    pub tls: Latency,
+
+    /// Time to first byte, i.e. from sending request till receiving response headers
+    pub ttfb: Latency,
+
+    /// Reading response body
+    pub transfer: Latency,
+}
+
+impl Phases {
+    /// Accounts timings of a single request
+    pub fn record(&mut self, result: &RequestResult) {
+        if let Some(dns) = result.dns {
+            self.dns.record(dns);
+        }
+        if let Some(connect) = result.connect {
+            self.connect.record(connect);
+        }
+        if let Some(tls) = result.tls {
+            self.tls.record(tls);
+        }
+        self.ttfb.record(result.ttfb);
+        self.transfer
+            .record(result.latency.saturating_sub(result.ttfb));
+    }
+
+    /// Returns all the phases along with their names in the order they happen
+    pub fn all(&self) -> Vec<(&'static str, &Latency)> {
+        vec![
+            ("dns", &self.dns),
+            ("connect", &self.connect),
+            ("tls", &self.tls),
+            ("ttfb", &self.ttfb),
+            ("transfer", &self.transfer),
+        ]
+    }
+}
+
+/// Results of requests sent to a single endpoint, i.e. request name
+#[derive(Debug, Clone, Default)]
+pub struct Endpoint {
+    /// Number of completed requests
+    pub requests: u64,
+
+    /// Number of failed requests
+    pub errors: u64,
+
+    /// Number of requests per final status code
+    pub status_codes: Map,
+
+    /// Time taken by requests including reading whole response body
+    pub latency: Latency,
+}
+
+impl Endpoint {
+    /// Accounts the result of a single request
+    pub fn record(&mut self, result: &RequestResult) {
+        self.requests += 1;
+        if result.is_failure() {
+            self.errors += 1;
+        }
+        *self.status_codes.entry(result.status).or_insert(0) += 1;
+        if result.status != 0 {
+            self.latency.record(result.latency);
+        }
+    }
+
+    /// Returns ratio of failed requests to all the requests
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Aggregated results of all the requests sent during a run
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Time when the run started
+    pub started_at: DateTime<Utc>,
+
+    /// Content of Data file the run was configured with
+    pub config: serde_json::Value,
+
+    /// Number of requests per final status code
+    pub status_codes: Map,
+
+    /// Number of requests per kind of error
+    pub errors: HashMap<String, u64>,
+
+    /// Number of followed redirect hops per redirect status code
+    pub redirect_codes: Map,
+
+    /// Results per endpoint, i.e. request name
+    pub endpoints: BTreeMap<String, Endpoint>,
+
+    /// Total size of request bodies sent
+    pub bytes_sent: u64,
+
+    /// Total size of response bodies received on the wire
+    pub bytes_received: u64,
+
+    /// Total size of response bodies after decompression
+    pub bytes_decoded: u64,
+
+    /// Time taken by requests including reading whole response body
+    pub latency: Latency,
+
+    /// Time taken by each phase of requests
+    pub phases: Phases,
+
+    /// Latency counted from intended send time of requests, only if sent at a fixed rate
+    pub corrected_latency: Latency,
+
+    /// Latency of requests completed since live progress was last shown
+    pub window: Latency,
+
+    /// Length of intervals results are aggregated in, zero to not aggregate per interval
+    pub interval: Duration,
+
+    /// Results per interval in the order of intervals
+    pub timeseries: Vec<Interval>,
+
+    /// Outcome of thresholds checked once the run is finished
+    pub thresholds: Vec<ThresholdResult>,
+
+    /// Time taken by the whole run
+    pub duration: Duration,
+}
+
+impl Report {
+    /// Accounts the result of a single request
+    pub fn record(&mut self, result: &RequestResult) {
+        *self.status_codes.entry(result.status).or_insert(0) += 1;
+        if let Some(error) = &result.error {
+            *self.errors.entry(error.clone()).or_insert(0) += 1;
+        }
+        for code in &result.redirects {
+            *self.redirect_codes.entry(*code).or_insert(0) += 1;
+        }
+        self.endpoints
+            .entry(result.endpoint.clone())
+            .or_default()
+            .record(result);
+        self.bytes_sent += result.bytes_sent;
+        self.bytes_received += result.bytes_received;
+        self.bytes_decoded += result.bytes_decoded;
+
+        // Requests that failed during a stall, e.g. timed out, are what corrected latency has to
+        // account, so it includes failed requests
+        if let Some(delay) = result.delay {
+            self.corrected_latency.record(result.latency + delay);
+        }
+
+        // Failed requests have no timings
+        if result.status != 0 {
+            self.latency.record(result.latency);
+            self.window.record(result.latency);
+            self.phases.record(result);
+        }
+
+        // Account in the interval the request completed in
+        if !self.interval.is_zero() {
+            let index = (result.completed_at.as_nanos() / self.interval.as_nanos()) as usize;
+            self.add_intervals(index + 1);
+            self.timeseries[index].record(result);
+        }
+    }
+
+    /// Adds empty intervals till there are supplied number of intervals
+    fn add_intervals(&mut self, count: usize) {
+        while self.timeseries.len() < count {
+            let start = self.interval * self.timeseries.len() as u32;
+            self.timeseries.push(Interval::new(start));
+            if self.timeseries.len() > OPEN_INTERVALS {
+                let closed = self.timeseries.len() - OPEN_INTERVALS - 1;
+                self.timeseries[closed].close();
+            }
+        }
+    }
+
+    /// Marks the end of the run. Intervals without any completed request till the end are added
+    /// too, so stalls at the end are visible.
+    pub fn finish(&mut self, duration: Duration) {
+        self.duration = duration;
+        if !self.interval.is_zero() {
+            let count = duration.as_nanos().div_ceil(self.interval.as_nanos());
+            self.add_intervals(count as usize);
+        }
+    }
+
+    /// Returns length of supplied interval, last interval might be shorter than others once the
+    /// run is finished
+    pub fn interval_length(&self, interval: &Interval) -> Duration {
+        if self.duration.is_zero() {
+            return self.interval;
+        }
+        self.interval
+            .min(self.duration.saturating_sub(interval.start))
+    }
+
+    /// Returns supplied total as a rate per second of the run
+    pub fn per_second(&self, total: u64) -> f64 {
+        let seconds = self.duration.as_secs_f64();
+        if seconds > 0.0 {
+            total as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns number of completed requests
+    pub fn completed(&self) -> u64 {
+        self.status_codes.values().map(|count| *count as u64).sum()
+    }
+
+    /// Returns number of failed requests including responses with error status codes
+    pub fn failed(&self) -> u64 {
+        self.status_codes
+            .iter()
+            .filter(|(code, _)| **code == 0 || **code >= 400)
+            .map(|(_, count)| *count as u64)
+            .sum()
+    }
+
+    /// Returns ratio of failed requests to all the requests
+    pub fn error_rate(&self) -> f64 {
+        let completed = self.completed();
+        if completed == 0 {
+            0.0
+        } else {
+            self.failed() as f64 / completed as f64
+        }
+    }
+
+    /// Returns total number of followed redirect hops
+    pub fn total_redirects(&self) -> i32 {
+        self.redirect_codes.values().sum()
+    }
+}
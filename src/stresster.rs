@@ -1,159 +1,731 @@
+use crate::arrival::{seeded_rng, Arrival, Schedule, SharedSchedule};
+use crate::compare::compare;
+use crate::compression::decoded_len;
+use crate::event_log::{read_report, Event, EventLog};
 use crate::helper::{
-    extract_values_from_args, get_cmd_args, get_logger, get_output_producer,
-    get_request_data_from_file,
+    extract_event_filter, extract_interval, extract_tolerances, extract_values_from_args,
+    get_cmd_args, get_logger, get_output_producer, get_request_data_from_file,
 };
-use crate::types::{Command, Countermap, Data, HttpMethods, Logger};
+use crate::history::{list_runs, show_run, History};
+use crate::metrics::serve_metrics;
+use crate::progress::show_progress;
+use crate::push::{push_metrics, Pusher, FINAL_PUSH_TIMEOUT};
+use crate::raw_log::RawLog;
+use crate::report::Report;
+use crate::timeseries::write_timeseries_csv;
+use crate::timing::{
+    native_roots, tls_config, ConnectTimingLayer, SharedTrace, TimingResolver, Trace,
+};
+use crate::trace_context::{Span, SpanContext, SpanExporter, SHUTDOWN_TIMEOUT};
+use crate::types::{
+    Command, Data, HttpMethods, Logger, Options, OutputFormat, RequestResult, SharedReport,
+};
+use anyhow::Context;
+use chrono::Utc;
 use futures::future::join_all;
-use reqwest::Client;
-use std::collections::HashMap;
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::{redirect, Client};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
 use std::fs;
 use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use tokio::net::TcpListener;
 use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+
+/// Maximum number of users sending at a fixed rate, unless supplied, so very high rates or numbers
+/// of requests don't exhaust memory with clients
+const MAX_RATE_USERS: f64 = 1000.0;
 
 #[derive(Clone, Debug)]
 pub struct Stresster {
     pub log_path: String,
 }
 
+/// Values shared by all the virtual users of a run
+#[derive(Clone)]
+struct RunContext {
+    sender: mpsc::Sender<Command>,
+    logger: Logger,
+    data: Data,
+    options: Arc<Options>,
+
+    /// Requests to hand out to virtual users
+    schedule: SharedSchedule,
+
+    /// Number of requests sent so far
+    sent: Arc<AtomicU64>,
+
+    /// Number of requests users started to build so far, per request log records are sampled by
+    attempts: Arc<AtomicU64>,
+
+    /// Start of the run
+    start: Instant,
+
+    /// Queue of client spans to export, if asked
+    spans: Option<mpsc::Sender<Span>>,
+
+    /// Queue of events to write to event log, if asked
+    events: Option<mpsc::Sender<Event>>,
+}
+
 impl Stresster {
-    /// Counts number of requests for all the received status code based on data received from Send
-    /// function.
+    /// Accounts results of all the requests in a report based on data received from Send
+    /// function. Result of every request is written to raw log if asked.
     async fn counting_machine(
-        counter_map: Countermap,
+        report: SharedReport,
+        mut raw_log: Option<RawLog>,
         mut rx: tokio::sync::mpsc::Receiver<Command>,
-    ) {
-        let mut map = counter_map.lock().await;
+    ) -> anyhow::Result<()> {
+        let mut failure = None;
         while let Some(cmd) = rx.recv().await {
             match cmd {
-                Command::Increment(code) => {
-                    if map.contains_key(&code) {
-                        let value = map.get(&code).unwrap();
-                        *map.get_mut(&code).unwrap() = value + 1;
-                    } else {
-                        map.insert(code, 1);
+                Command::Record(result) => {
+                    report.lock().await.record(&result);
+
+                    // Raw log stops at the first failure, which is reported after the run
+                    if let Some(log) = raw_log.as_mut() {
+                        if let Err(e) = log.write(&result) {
+                            failure = Some(e);
+                            raw_log = None;
+                        }
                     }
-                    // map.entry(100).or_insert(&0) += 1;
                 }
                 Command::Exit => {
-                    return;
+                    break;
                 }
             };
         }
+        match failure {
+            Some(e) => Err(e),
+            None => raw_log.map_or(Ok(()), RawLog::finish),
+        }
+    }
+
+    /// Builds a client with configurations from Data file. Followed redirects and connection
+    /// phase timings are recorded in returned trace.
+    fn build_client(data: &Data) -> (Client, SharedTrace) {
+        // Follow redirects up to configured limit and remember status code of every hop
+        let max_redirects = data.max_redirects;
+        let trace = SharedTrace::default();
+        let trace_clone = trace.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            // First URL in chain is the original request
+            if attempt.previous().len() > max_redirects {
+                attempt.stop()
+            } else {
+                trace_clone
+                    .lock()
+                    .unwrap()
+                    .redirects
+                    .push(attempt.status().as_u16());
+                attempt.follow()
+            }
+        });
+
+        // Build client with required configurations
+        let mut builder = Client::builder()
+            .default_headers(data.headers.clone())
+            .redirect(policy)
+            .cookie_store(data.cookies)
+            .dns_resolver(Arc::new(TimingResolver {
+                trace: trace.clone(),
+            }))
+            .connector_layer(ConnectTimingLayer {
+                trace: trace.clone(),
+            });
+
+        // Trust system certificates along with the configured one
+        let mut roots = native_roots();
+        let ssl_cert = &data.cert_path;
+        if !ssl_cert.is_empty() {
+            let certs = CertificateDer::pem_file_iter(ssl_cert)
+                .unwrap_or_else(|_| panic!("ERROR: Error reading {:?}", ssl_cert))
+                .collect::<Result<Vec<_>, _>>()
+                .expect("ERROR: Error reading file");
+            roots.add_parsable_certificates(certs);
+        }
+        builder = builder.use_preconfigured_tls(tls_config(roots, trace.clone()));
+
+        // Route requests through configured proxies
+        for proxy in &data.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+        (builder.build().unwrap(), trace)
+    }
+
+    /// Simulates a virtual user. It sends requests one after another over its own client till
+    /// all the requests of the run are sent, so connections and cookies are shared only
+    /// between requests of the same user. The user thinks between its requests if asked, before
+    /// it takes the next one. At a fixed rate, every request waits for its intended send time,
+    /// and is sent late if all the users are busy with earlier requests or thinking.
+    async fn virtual_user(user: i32, client: Client, trace: SharedTrace, context: RunContext) {
+        // Every user gets its own sequence of think times
+        let mut rng = seeded_rng(
+            context
+                .options
+                .seed
+                .map(|seed| seed.wrapping_add(user as u64)),
+        );
+        let mut first = true;
+        loop {
+            // Think before taking the next request, so a thinking user neither holds a request
+            // another user could send nor gets its think time counted as delay
+            if let (false, Some(think_time)) = (first, &context.options.think_time) {
+                if context.schedule.lock().unwrap().is_finished() {
+                    break;
+                }
+                tokio::time::sleep(think_time.sample(&mut rng)).await;
+            }
+            first = false;
+            let next = context.schedule.lock().unwrap().next();
+            let intended = match next {
+                Some(intended) => intended.map(|intended| context.start + intended),
+                None => break,
+            };
+            if let Some(intended) = intended {
+                tokio::time::sleep_until(intended.into()).await;
+            }
+            Self::send(user, &client, &trace, &context, intended).await;
+        }
     }
 
     /// Actual sends the{GET, POST, PUT, PATCH, DELETE} requests to URL configured in Data file.
     /// Sends return code to couting_machine function for accouting.
-    async fn send(sender: tokio::sync::mpsc::Sender<Command>, logger: Logger, data: Data) {
+    async fn send(
+        user: i32,
+        client: &Client,
+        trace: &SharedTrace,
+        context: &RunContext,
+        intended: Option<Instant>,
+    ) {
         // Common vars
-        let logger = logger.clone();
+        let logger = context.logger.clone();
+        let sender = &context.sender;
+        let options = &context.options;
 
-        let data = data.clone();
+        let data = context.data.clone();
         let payload = &data.payload;
-        let headers = data.headers.clone();
         let method = data.method.clone();
         let target_url = data.url.to_owned();
-        let ssl_cert = &data.cert_path;
-        info!(
-            logger,
-            "Sending {:?} request to {:?} with payload {:?}", method, target_url, payload
-        );
+        let bytes_sent = serde_json::to_vec(payload).map_or(0, |body| body.len() as u64);
+        let number = context.attempts.fetch_add(1, Ordering::SeqCst);
 
-        // Build client with required configurations
-        let mut client = Client::builder()
-            .default_headers(headers.clone())
-            .build()
-            .unwrap();
+        // Event log replaces per request records in log, which are sampled otherwise
+        let sampled = context.events.is_none() && number.is_multiple_of(options.log.sample);
+        if sampled {
+            info!(
+                logger,
+                "User {} sending {:?} request to {:?}", user, method, target_url
+            );
+            debug!(logger, "Payload {:?}", payload);
+        }
 
-        if !ssl_cert.is_empty() {
-            let mut buf = Vec::new();
-            fs::File::open(ssl_cert)
-                .unwrap_or_else(|_| panic!("ERROR: Error reading {:?}", ssl_cert))
-                .read_to_end(&mut buf)
-                .expect("ERROR: Error reading file");
-            let cert = reqwest::Certificate::from_pem(&buf).unwrap();
-            client = Client::builder()
-                .add_root_certificate(cert)
-                .default_headers(headers)
-                .build()
-                .unwrap();
-        }
-        let result = match method {
-            HttpMethods::Get => client.get(&*target_url).json(payload).send().await,
-            HttpMethods::Post => client.post(&*target_url).json(payload).send().await,
-            HttpMethods::Put => client.put(&*target_url).json(payload).send().await,
-            HttpMethods::Delete => client.delete(&*target_url).json(payload).send().await,
-            HttpMethods::Patch => client.patch(&*target_url).json(payload).send().await,
-        };
-        match result {
-            Ok(r) => {
-                let command = Command::Increment(r.status().as_u16());
-                info!(logger, "Result status code: {}", r.status().as_u16());
-                sender.send(command).await.unwrap();
+        let mut request = match method {
+            HttpMethods::Get => client.get(&*target_url),
+            HttpMethods::Post => client.post(&*target_url),
+            HttpMethods::Put => client.put(&*target_url),
+            HttpMethods::Delete => client.delete(&*target_url),
+            HttpMethods::Patch => client.patch(&*target_url),
+        }
+        .json(payload);
+
+        // Propagate trace context so server side traces of the request can be found
+        let span_context = (data.trace || context.spans.is_some()).then(SpanContext::new);
+        if let Some(span_context) = &span_context {
+            request = request.header("traceparent", span_context.traceparent());
+        }
+
+        let started_at = SystemTime::now();
+        let started = Instant::now();
+        let delay = intended.map(|intended| started.saturating_duration_since(intended));
+        context.sent.fetch_add(1, Ordering::SeqCst);
+        let result = request.send().await;
+        let Trace {
+            redirects,
+            dns,
+            connect,
+            tls,
+            ..
+        } = std::mem::take(&mut *trace.lock().unwrap());
+
+        // Connect phase is reported without DNS lookup
+        let connect = connect.map(|connect| connect.saturating_sub(dns.unwrap_or_default()));
+        let result = match result {
+            Ok(mut r) => {
+                let ttfb = started.elapsed();
+                let status = r.status().as_u16();
+                let encoding = r
+                    .headers()
+                    .get(CONTENT_ENCODING)
+                    .and_then(|encoding| encoding.to_str().ok())
+                    .unwrap_or("identity")
+                    .to_owned();
+                if sampled {
+                    info!(logger, "Result status code: {}", status);
+                }
+
+                /* Read whole body to include transfer time in latency and to let connection
+                 * be reused. Body is kept only if it has to be decompressed or captured.
+                 */
+                let capture = options.capture_dir.is_some() && status >= 400;
+                let keep_body = capture || encoding != "identity";
+                let mut body = vec![];
+                let mut bytes_received = 0;
+                let mut error = None;
+                loop {
+                    match r.chunk().await {
+                        Ok(Some(chunk)) => {
+                            bytes_received += chunk.len() as u64;
+                            if keep_body {
+                                body.extend_from_slice(&chunk);
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            error!(logger, "Failed to read response body : {}", e);
+                            error = Some(error_kind(&e).to_owned());
+                            break;
+                        }
+                    }
+                }
+                let latency = started.elapsed();
+
+                if let (true, Some(capture_dir)) = (capture, &options.capture_dir) {
+                    let path = capture_dir.join(format!("{}_{}.body", status, Uuid::new_v4()));
+                    if let Err(e) = tokio::fs::write(&path, &body).await {
+                        error!(
+                            logger,
+                            "Failed to capture body to {} : {}",
+                            path.display(),
+                            e
+                        );
+                    }
+                }
+
+                // Decompression is CPU bound so it's kept off the async workers
+                let bytes_decoded = if keep_body {
+                    tokio::task::spawn_blocking(move || decoded_len(&encoding, &body))
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result)
+                        .unwrap_or_else(|e| {
+                            error!(logger, "{:#}", e);
+                            0
+                        })
+                } else {
+                    bytes_received
+                };
+
+                RequestResult {
+                    endpoint: data.name.clone(),
+                    status,
+                    error,
+                    redirects,
+                    bytes_sent,
+                    bytes_received,
+                    bytes_decoded,
+                    latency,
+                    ttfb,
+                    dns,
+                    connect,
+                    tls,
+                    completed_at: context.start.elapsed(),
+                    delay,
+                }
             }
             Err(e) => {
-                let command = Command::Increment(0);
                 error!(logger, "Result error : {}", e);
-                sender.send(command).await.unwrap();
+                RequestResult {
+                    endpoint: data.name.clone(),
+                    status: 0,
+                    error: Some(error_kind(&e).to_owned()),
+                    redirects,
+                    latency: started.elapsed(),
+                    completed_at: context.start.elapsed(),
+                    delay,
+                    ..Default::default()
+                }
+            }
+        };
+
+        // Spans are dropped rather than slowing down the run if collector can't keep up
+        let method = format!("{:?}", method).to_uppercase();
+        if let (Some(spans), Some(span_context)) = (&context.spans, span_context) {
+            let span = Span {
+                context: span_context,
+                name: format!("{} {}", method, data.name),
+                method: method.clone(),
+                url: target_url.clone(),
+                start: started_at,
+                end: SystemTime::now(),
+                status: result.status,
+                error: result.error.clone(),
+            };
+            if spans.try_send(span).is_err() {
+                debug!(logger, "Dropped span as export queue is full");
             }
         }
+
+        // Unlike spans, events are never dropped so the log can be reanalysed
+        if let Some(events) = &context.events {
+            let event = Event {
+                timestamp: started_at.into(),
+                user,
+                name: data.name.clone(),
+                method,
+                url: target_url,
+                result: result.clone(),
+            };
+            let _ = events.send(event).await;
+        }
+        sender
+            .send(Command::Record(Box::new(result)))
+            .await
+            .unwrap();
     }
 
-    pub async fn run(self) -> anyhow::Result<()> {
+    /// Runs the command supplied on command line and returns exit code of the process. Failing
+    /// the checks of a run, e.g. thresholds, exits with 1.
+    pub async fn run(self) -> anyhow::Result<ExitCode> {
         // Generate command line args
         let matches = get_cmd_args().await;
 
+        // Compare results of earlier runs instead of running if asked
+        if let Some(args) = matches.subcommand_matches("compare") {
+            let regressed = compare(
+                Path::new(args.value_of("old").unwrap()),
+                Path::new(args.value_of("new").unwrap()),
+                &extract_tolerances(args)?,
+            )?;
+            if regressed {
+                return Ok(ExitCode::FAILURE);
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        // Rebuild results from an event log instead of running if asked
+        if let Some(args) = matches.subcommand_matches("report") {
+            let report = read_report(
+                Path::new(args.value_of("events").unwrap()),
+                &extract_event_filter(args)?,
+                extract_interval(args)?,
+            )?;
+            let producer =
+                get_output_producer(OutputFormat::from(args.value_of("format").unwrap())).await;
+            let logger = Arc::new(slog::Logger::root(slog::Discard, o!()));
+            let mut out = std::io::stdout();
+            producer
+                .produce(Arc::new(Mutex::new(report)), logger, &mut out)
+                .await?;
+            out.flush().context("Failed to write output")?;
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        // Show recorded runs instead of running if asked
+        if let Some(args) = matches.subcommand_matches("history") {
+            if let Some(args) = args.subcommand_matches("list") {
+                let limit = args
+                    .value_of("limit")
+                    .unwrap()
+                    .parse()
+                    .with_context(|| "Failed to parse `limit` argument".to_string())?;
+                list_runs(
+                    Path::new(args.value_of("database").unwrap()),
+                    args.value_of("target"),
+                    limit,
+                )?;
+            } else if let Some(args) = args.subcommand_matches("show") {
+                let id = args
+                    .value_of("id")
+                    .unwrap()
+                    .parse()
+                    .with_context(|| "Failed to parse `id` argument".to_string())?;
+                show_run(
+                    Path::new(args.value_of("database").unwrap()),
+                    id,
+                    args.is_present("json"),
+                )?;
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+
         // Extract user supplied values
-        let (output_format, config_filename, total_requests) =
-            extract_values_from_args(matches).await?;
+        let options = extract_values_from_args(matches).await?;
+        let total_requests = options.total_requests;
 
         // Create RequestData from data file
-        let request_data = get_request_data_from_file(&config_filename).await?;
+        let request_data = get_request_data_from_file(&options.config_filename).await?;
         let shared_data = Arc::new(request_data);
 
+        // Create output files beforehand so a wrong path doesn't waste the whole run
+        let mut sinks = options
+            .outputs
+            .iter()
+            .map(
+                |output| -> anyhow::Result<(OutputFormat, Box<dyn Write + Send>)> {
+                    let sink: Box<dyn Write + Send> = match &output.path {
+                        Some(path) => {
+                            Box::new(BufWriter::new(fs::File::create(path).with_context(
+                                || format!("Failed to create output file {}", path.display()),
+                            )?))
+                        }
+                        None => Box::new(std::io::stdout()),
+                    };
+                    Ok((output.format.clone(), sink))
+                },
+            )
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let timeseries_csv = options
+            .timeseries_csv
+            .as_ref()
+            .map(|path| {
+                fs::File::create(path)
+                    .with_context(|| format!("Failed to create CSV file {}", path.display()))
+            })
+            .transpose()?;
+
+        let history = options
+            .save_history
+            .as_ref()
+            .map(|path| History::create(path))
+            .transpose()?;
+
+        let started_at = Utc::now();
+        let raw_log = options
+            .raw_log
+            .as_ref()
+            .map(|path| RawLog::create(path, started_at))
+            .transpose()?;
+
         // Variables shared between tasks
-        let counter = Arc::new(Mutex::new(HashMap::new())); // Map of Atomic counters to keep HTTP status code count
+        let report = Arc::new(Mutex::new(Report {
+            started_at,
+            config: shared_data.config.clone(),
+            interval: options.interval,
+            ..Default::default()
+        })); // Aggregated results of all the requests
+        let options = Arc::new(options);
         let (sender, receiver) = mpsc::channel(50);
 
         // Create a logger instance
-        let logger = get_logger(&self.log_path).await?;
+        let logger = get_logger(&options.log, &self.log_path).await?;
         let shared_logger = Arc::new(logger);
 
+        // Serve live metrics if asked
+        let sent = Arc::new(AtomicU64::new(0));
+        let metrics_handle = match &options.metrics_listen {
+            Some(address) => {
+                let listener = TcpListener::bind(address)
+                    .await
+                    .with_context(|| format!("Failed to listen for metrics on {}", address))?;
+                Some(tokio::spawn(serve_metrics(
+                    listener,
+                    report.clone(),
+                    sent.clone(),
+                )))
+            }
+            None => None,
+        };
+
+        // Push results per interval to metrics backends if asked
+        let pusher = Pusher::new(&options, &shared_data.url, shared_logger.clone())
+            .await?
+            .map(|pusher| Arc::new(Mutex::new(pusher)));
+
         // Start counter function
-        let counter_clone = counter.clone();
-        let task = Self::counting_machine(counter_clone, receiver);
+        let report_clone = report.clone();
+        let task = Self::counting_machine(report_clone, raw_log, receiver);
         let counting_machine_handle = tokio::spawn(task);
 
-        let mut index: i32 = 1;
-        let mut handles = vec![];
-        loop {
-            let sender = sender.clone();
-            let logger = shared_logger.clone();
-            let shared_data = shared_data.clone();
-            handles.push(Self::send(sender, logger, shared_data));
-
-            if total_requests != 0 {
-                if index == total_requests {
-                    break;
-                } else {
-                    index += 1;
-                }
+        // Every request gets its own user unless number of users is supplied, or as many users as
+        // the rate when sending at a fixed rate
+        let rate_users = options.rate.map(|rate| {
+            let users = match options.arrival {
+                Arrival::Bursty(size) => rate.ceil().max(size as f64),
+                _ => rate.ceil(),
+            };
+            users.min(MAX_RATE_USERS) as i32
+        });
+        let users = match (options.users, total_requests) {
+            (0, 0) => rate_users.unwrap_or(1),
+            (0, total_requests) => {
+                rate_users.map_or(total_requests, |users| users.min(total_requests))
             }
+            (users, 0) => users,
+            (users, total_requests) => users.min(total_requests),
+        };
+
+        // Export client spans to OpenTelemetry collector if asked
+        let span_exporter = options
+            .otlp_endpoint
+            .as_ref()
+            .map(|endpoint| SpanExporter::start(endpoint, shared_logger.clone()))
+            .transpose()?;
+
+        // Write an event of every request if asked
+        let event_log = match &options.event_log {
+            Some(path) => Some(EventLog::start(path).await?),
+            None => None,
+        };
+
+        // Clients are built beforehand so building them doesn't delay requests of the run
+        let clients = (0..users)
+            .map(|_| Self::build_client(&shared_data))
+            .collect::<Vec<_>>();
+
+        let start = Instant::now();
+        let context = RunContext {
+            sender: sender.clone(),
+            logger: shared_logger.clone(),
+            data: shared_data.clone(),
+            options: options.clone(),
+            schedule: Arc::new(std::sync::Mutex::new(Schedule::new(
+                total_requests as u64,
+                options.rate.map(|rate| (rate, options.arrival.clone())),
+                options.seed,
+            ))),
+            sent,
+            attempts: Arc::new(AtomicU64::new(0)),
+            start,
+            spans: span_exporter.as_ref().map(SpanExporter::sender),
+            events: event_log.as_ref().map(EventLog::sender),
+        };
+
+        let push_handle = pusher.as_ref().map(|pusher| {
+            tokio::spawn(push_metrics(
+                pusher.clone(),
+                report.clone(),
+                options.interval,
+                start,
+            ))
+        });
+
+        // Show live progress unless asked to be quiet
+        let progress_handle = (!options.quiet)
+            .then(|| tokio::spawn(show_progress(report.clone(), context.sent.clone(), start)));
+
+        let mut handles = vec![];
+        for (user, (client, trace)) in (1..).zip(clients) {
+            handles.push(Self::virtual_user(user, client, trace, context.clone()));
         }
 
         join_all(handles).await;
+        drop(context);
+        // A collector that hangs mustn't hold back results of the run
+        if let Some(span_exporter) = span_exporter {
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, span_exporter.shutdown())
+                .await
+                .is_err()
+            {
+                warn!(
+                    shared_logger,
+                    "Timed out exporting spans, spans not exported yet are dropped"
+                );
+            }
+        }
+        let event_log_result = match event_log {
+            Some(event_log) => event_log.shutdown().await,
+            None => Ok(()),
+        };
+        if let Some(progress_handle) = progress_handle {
+            progress_handle.abort();
+        }
+        if let Some(push_handle) = push_handle {
+            push_handle.abort();
+        }
         sender.send(Command::Exit).await.unwrap();
-        let _ = counting_machine_handle.await;
+        let raw_log_result = counting_machine_handle.await?;
+        {
+            let mut report = report.lock().await;
+            report.finish(start.elapsed());
+            report.thresholds = shared_data
+                .thresholds
+                .iter()
+                .map(|threshold| threshold.evaluate(&report))
+                .collect();
+        }
+
+        // A backend that hangs mustn't hold back results of the run
+        if let Some(pusher) = &pusher {
+            let mut pusher = pusher.lock().await;
+            if tokio::time::timeout(FINAL_PUSH_TIMEOUT, pusher.push_remaining(&report))
+                .await
+                .is_err()
+            {
+                warn!(
+                    shared_logger,
+                    "Timed out pushing metrics, intervals not pushed yet are dropped"
+                );
+            }
+        }
 
-        let c = counter.clone();
-        let logger = shared_logger.clone();
+        // Results are still produced if the run can't be recorded
+        if let (Some(history), Some(path)) = (&history, &options.save_history) {
+            match history.save_run(&*report.lock().await) {
+                Ok(id) => info!(shared_logger, "Saved run {} to {}", id, path.display()),
+                Err(e) => error!(shared_logger, "{:#}", e),
+            }
+        }
+
+        if let Some(file) = timeseries_csv {
+            write_timeseries_csv(&*report.lock().await, BufWriter::new(file))?;
+        }
 
         /* Output based on format. We don't need to worry about formats other than specified.
            Clap's argument parser will take care of that.
         */
-        let producer = get_output_producer(output_format).await;
-        let _ = producer.produce(c, logger).await?;
+        for (output_format, sink) in sinks.iter_mut() {
+            let producer = get_output_producer(output_format.clone()).await;
+            producer
+                .produce(report.clone(), shared_logger.clone(), sink.as_mut())
+                .await?;
+            sink.flush().context("Failed to write output")?;
+        }
+        // Keep serving final results long enough for Prometheus to scrape them
+        if let Some(metrics_handle) = metrics_handle {
+            tokio::time::sleep(options.metrics_linger).await;
+            metrics_handle.abort();
+        }
+
+        raw_log_result?;
+        event_log_result?;
+
+        // Failed thresholds fail the run so CI can gate on them
+        if report
+            .lock()
+            .await
+            .thresholds
+            .iter()
+            .any(|result| !result.passed)
+        {
+            return Ok(ExitCode::FAILURE);
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
 
-        Ok(())
+/// Classifies a failed request or body read into a short kind used in the report
+fn error_kind(e: &reqwest::Error) -> &'static str {
+    if e.is_timeout() {
+        "timeout"
+    } else if e.is_connect() {
+        "connect"
+    } else if e.is_redirect() {
+        "redirect"
+    } else if e.is_body() {
+        "body"
+    } else if e.is_decode() {
+        "decode"
+    } else if e.is_request() {
+        "request"
+    } else {
+        "other"
     }
 }
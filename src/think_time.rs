@@ -0,0 +1,57 @@
+use anyhow::{anyhow, Context};
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Normal};
+use std::time::Duration;
+
+/// Time a virtual user waits between its requests
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThinkTime {
+    Fixed(Duration),
+
+    /// Uniformly distributed between minimum and maximum
+    Uniform(Duration, Duration),
+
+    /// Normally distributed with mean and standard deviation, negative samples are zero
+    Normal(Duration, Duration),
+
+    /// Exponentially distributed with mean
+    Exponential(Duration),
+}
+
+impl ThinkTime {
+    /// Parses a think time in the form of `duration`, `uniform:min,max`, `normal:mean,std_dev`
+    /// or `exponential:mean`, e.g. `uniform:1s,3s`
+    pub fn parse(spec: &str) -> anyhow::Result<ThinkTime> {
+        let (distribution, params) = spec.split_once(':').unwrap_or(("fixed", spec));
+        let params = params
+            .split(',')
+            .map(|param| {
+                humantime::parse_duration(param.trim())
+                    .with_context(|| format!("Invalid duration {} in think time {}", param, spec))
+            })
+            .collect::<anyhow::Result<Vec<Duration>>>()?;
+        let think_time = match (distribution, params.as_slice()) {
+            ("fixed", [duration]) => ThinkTime::Fixed(*duration),
+            ("uniform", [min, max]) if min <= max => ThinkTime::Uniform(*min, *max),
+            ("normal", [mean, std_dev]) => ThinkTime::Normal(*mean, *std_dev),
+            ("exponential", [mean]) if !mean.is_zero() => ThinkTime::Exponential(*mean),
+            _ => return Err(anyhow!("Invalid think time {}", spec)),
+        };
+        Ok(think_time)
+    }
+
+    /// Returns a random think time from the distribution
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        let seconds = match self {
+            ThinkTime::Fixed(duration) => return *duration,
+            ThinkTime::Uniform(min, max) => rng.gen_range(min.as_secs_f64()..=max.as_secs_f64()),
+            ThinkTime::Normal(mean, std_dev) => {
+                Normal::new(mean.as_secs_f64(), std_dev.as_secs_f64())
+                    .unwrap()
+                    .sample(rng)
+            }
+            ThinkTime::Exponential(mean) => Exp::new(1.0 / mean.as_secs_f64()).unwrap().sample(rng),
+        };
+        Duration::from_secs_f64(seconds.max(0.0))
+    }
+}
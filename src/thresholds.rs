@@ -0,0 +1,122 @@
+use crate::report::{Report, PERCENTILES};
+use anyhow::anyhow;
+use serde_json::Value;
+
+/// Metrics a threshold can be set on. Latency statistics are in milliseconds and error rate is
+/// a ratio of failed requests. All of them are upper limits except `rps`.
+pub const THRESHOLD_METRICS: [&str; 9] = [
+    "min",
+    "mean",
+    "p50",
+    "p90",
+    "p95",
+    "p99",
+    "max",
+    "error_rate",
+    "rps",
+];
+
+/// A limit on a metric of the run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    pub metric: String,
+    pub limit: f64,
+}
+
+/// Outcome of checking a threshold against results of the run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdResult {
+    pub threshold: Threshold,
+    pub observed: f64,
+    pub passed: bool,
+}
+
+impl Threshold {
+    /// Returns true if observed value has to be at least the limit rather than at most
+    pub fn is_minimum(&self) -> bool {
+        self.metric == "rps"
+    }
+
+    /// Returns unit of the metric to be shown along with values
+    pub fn unit(&self) -> &'static str {
+        match self.metric.as_str() {
+            "error_rate" => "",
+            "rps" => " req/s",
+            _ => " ms",
+        }
+    }
+
+    /// Returns the threshold in human readable form, e.g. `p95 <= 200 ms`
+    pub fn describe(&self) -> String {
+        let operator = if self.is_minimum() { ">=" } else { "<=" };
+        format!("{} {} {}{}", self.metric, operator, self.limit, self.unit())
+    }
+
+    /// Returns true if the metric is a latency statistic
+    pub fn is_latency(&self) -> bool {
+        !matches!(self.metric.as_str(), "error_rate" | "rps")
+    }
+
+    /// Checks the threshold against supplied report. Latency thresholds fail if no request
+    /// succeeded, as there's no latency to check.
+    pub fn evaluate(&self, report: &Report) -> ThresholdResult {
+        let observed = match self.metric.as_str() {
+            "min" => report.latency.min(),
+            "mean" => report.latency.mean(),
+            "max" => report.latency.max(),
+            "error_rate" => report.error_rate(),
+            "rps" => report.per_second(report.completed()),
+            metric => PERCENTILES
+                .iter()
+                .find(|(label, _)| *label == metric)
+                .map_or(0.0, |(_, percentile)| {
+                    report.latency.percentile(*percentile)
+                }),
+        };
+        let passed = if self.is_latency() && report.latency.count() == 0 {
+            false
+        } else if self.is_minimum() {
+            observed >= self.limit
+        } else {
+            observed <= self.limit
+        };
+        ThresholdResult {
+            threshold: self.clone(),
+            observed,
+            passed,
+        }
+    }
+}
+
+/// Builds thresholds from `thresholds` section of Data file, an object mapping metrics to their
+/// limits, e.g. `{"p95": 200, "error_rate": 0.01}`.
+pub fn get_thresholds(config: Option<&Value>) -> anyhow::Result<Vec<Threshold>> {
+    let config = match config {
+        None => return Ok(vec![]),
+        Some(Value::Object(config)) => config,
+        Some(_) => {
+            return Err(anyhow!(
+                "Invalid thresholds, use an object of metric: limit"
+            ))
+        }
+    };
+    config
+        .iter()
+        .map(|(metric, limit)| {
+            if !THRESHOLD_METRICS.contains(&metric.as_str()) {
+                return Err(anyhow!(
+                    "Invalid threshold {}, supported metrics are {}",
+                    metric,
+                    THRESHOLD_METRICS.join(", ")
+                ));
+            }
+            let limit = limit
+                .as_f64()
+                .ok_or_else(|| anyhow!("Invalid limit {} of threshold {}", limit, metric))?;
+            Ok(Threshold {
+                metric: metric.clone(),
+                limit,
+            })
+        })
+        .collect()
+}
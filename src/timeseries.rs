@@ -0,0 +1,90 @@
+use crate::report::{Interval, Report};
+use anyhow::Context;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+use std::io::Write;
+
+/// Returns requests completed per second during supplied interval
+pub fn rps(report: &Report, interval: &Interval) -> f64 {
+    let seconds = report.interval_length(interval).as_secs_f64();
+    if seconds > 0.0 {
+        interval.requests as f64 / seconds
+    } else {
+        0.0
+    }
+}
+
+/// Returns results per interval as a JSON array
+pub fn timeseries_json(report: &Report) -> Value {
+    report
+        .timeseries
+        .iter()
+        .map(|interval| {
+            let latency = interval
+                .latency_summary()
+                .into_iter()
+                .map(|(label, value)| (label.to_string(), json!(value)))
+                .collect::<Map<String, Value>>();
+            json!({
+                "start": interval.start.as_secs_f64(),
+                "requests": interval.requests,
+                "rps": rps(report, interval),
+                "errors": interval.errors,
+                "error_rate": interval.error_rate(),
+                "latency": latency,
+                "status_codes": interval.status_codes,
+            })
+        })
+        .collect()
+}
+
+/// Writes results per interval as CSV. Every status code received during the run gets its own
+/// column.
+pub fn write_timeseries_csv<W: Write>(report: &Report, writer: W) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let status_codes = report
+        .timeseries
+        .iter()
+        .flat_map(|interval| interval.status_codes.keys().copied())
+        .collect::<BTreeSet<u16>>();
+
+    let mut header = vec![
+        "start".to_string(),
+        "requests".to_string(),
+        "rps".to_string(),
+        "errors".to_string(),
+        "error_rate".to_string(),
+    ];
+    header.extend(
+        report
+            .latency
+            .summary()
+            .into_iter()
+            .map(|(label, _)| format!("{}_ms", label)),
+    );
+    header.extend(status_codes.iter().map(|code| format!("status_{}", code)));
+    writer.write_record(&header)?;
+
+    for interval in &report.timeseries {
+        let mut record = vec![
+            format!("{:.3}", interval.start.as_secs_f64()),
+            interval.requests.to_string(),
+            format!("{:.3}", rps(report, interval)),
+            interval.errors.to_string(),
+            format!("{:.4}", interval.error_rate()),
+        ];
+        record.extend(
+            interval
+                .latency_summary()
+                .into_iter()
+                .map(|(_, value)| format!("{:.3}", value)),
+        );
+        record.extend(
+            status_codes
+                .iter()
+                .map(|code| interval.status_codes.get(code).unwrap_or(&0).to_string()),
+        );
+        writer.write_record(&record)?;
+    }
+    writer.flush().context("Failed to write timeseries CSV")
+}
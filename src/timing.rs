@@ -0,0 +1,203 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Client, ClientBuilder};
+use rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, Resumption, Tls12ClientSessionValue,
+    Tls13ClientSessionValue,
+};
+use rustls::crypto::ring;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, NamedGroup, RootCertStore};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Details recorded by hooks of a client about the request in progress
+#[derive(Debug, Default)]
+pub struct Trace {
+    /// Status codes of followed redirects
+    pub redirects: Vec<u16>,
+
+    /// Time taken to resolve host names, present only if a new connection was opened
+    pub dns: Option<Duration>,
+
+    /// Time taken to open new connections including DNS lookup but excluding TLS handshake
+    pub connect: Option<Duration>,
+
+    /// Time taken by TLS handshakes of new connections
+    pub tls: Option<Duration>,
+
+    /// Time the TLS handshake of the connection being opened started at
+    tls_started: Option<Instant>,
+}
+
+pub type SharedTrace = Arc<Mutex<Trace>>;
+
+/// Adds a duration to a phase which might be repeated because of redirects
+fn add(phase: &mut Option<Duration>, duration: Duration) {
+    *phase = Some(phase.unwrap_or_default() + duration);
+}
+
+/// DNS resolver that records time taken by every lookup in a trace
+pub struct TimingResolver {
+    pub trace: SharedTrace,
+}
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let trace = self.trace.clone();
+        // Port is replaced by the connector
+        let host = format!("{}:0", name.as_str());
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs = tokio::net::lookup_host(host).await?;
+            add(&mut trace.lock().unwrap().dns, started.elapsed());
+            Ok(Box::new(addrs) as Addrs)
+        })
+    }
+}
+
+/// Connector layer that records time taken to open every new connection in a trace
+#[derive(Clone)]
+pub struct ConnectTimingLayer {
+    pub trace: SharedTrace,
+}
+
+impl<S> Layer<S> for ConnectTimingLayer {
+    type Service = ConnectTiming<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectTiming {
+            inner,
+            trace: self.trace.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ConnectTiming<S> {
+    inner: S,
+    trace: SharedTrace,
+}
+
+impl<S, R> Service<R> for ConnectTiming<S>
+where
+    S: Service<R>,
+    S::Response: Send + 'static,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let trace = self.trace.clone();
+        let started = Instant::now();
+        trace.lock().unwrap().tls_started = None;
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            let connection = connecting.await?;
+            let connected = Instant::now();
+            let trace = &mut *trace.lock().unwrap();
+            match trace.tls_started.take() {
+                Some(tls_started) => {
+                    add(&mut trace.connect, tls_started - started);
+                    add(&mut trace.tls, connected - tls_started);
+                }
+                None => add(&mut trace.connect, connected - started),
+            }
+            Ok(connection)
+        })
+    }
+}
+
+/// Returns a store of certificates trusted by the system
+pub fn native_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+    roots
+}
+
+/// Returns a client builder trusting system certificates, for backends results are sent to
+pub fn backend_client() -> ClientBuilder {
+    Client::builder().use_preconfigured_tls(plain_tls_config(native_roots()))
+}
+
+/// Returns TLS configuration trusting supplied certificates
+fn plain_tls_config(roots: RootCertStore) -> ClientConfig {
+    ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("ERROR: Unsupported TLS protocol versions")
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+/// Returns TLS configuration trusting supplied certificates, which records start of every TLS
+/// handshake in a trace so it can be timed apart from TCP connect
+pub fn tls_config(roots: RootCertStore, trace: SharedTrace) -> ClientConfig {
+    let mut config = plain_tls_config(roots);
+    config.resumption = Resumption::store(Arc::new(TlsTimingStore {
+        trace,
+        cache: ClientSessionMemoryCache::new(256),
+    }));
+    config
+}
+
+/// TLS session cache that records start of TLS handshakes in a trace. A handshake looks for a
+/// session to resume first thing after the TCP connection is open.
+#[derive(Debug)]
+struct TlsTimingStore {
+    trace: SharedTrace,
+    cache: ClientSessionMemoryCache,
+}
+
+impl ClientSessionStore for TlsTimingStore {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.cache.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        self.cache.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.cache.set_tls12_session(server_name, value)
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        self.cache.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.cache.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        self.cache.insert_tls13_ticket(server_name, value)
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        // Handshake through a proxy tunnel starts with the one to the proxy
+        self.trace
+            .lock()
+            .unwrap()
+            .tls_started
+            .get_or_insert_with(Instant::now);
+        self.cache.take_tls13_ticket(server_name)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::timing::backend_client;
+use crate::types::Logger;
+use anyhow::Context;
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Maximum number of spans sent to collector in a single request
+const MAX_BATCH_SIZE: usize = 512;
+
+/// Maximum time a span waits in a batch before it's sent to collector
+const BATCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Number of spans waiting to be exported after which new spans are dropped
+const QUEUE_SIZE: usize = 8192;
+
+/// Maximum time a single batch is sent to collector in
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Maximum time the run waits for queued spans to be exported once it's finished
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// W3C trace context of a single request
+#[derive(Debug, Clone)]
+pub struct SpanContext {
+    /// 16 bytes trace id in lowercase hex
+    pub trace_id: String,
+
+    /// 8 bytes span id in lowercase hex
+    pub span_id: String,
+}
+
+impl SpanContext {
+    /// Returns a new context with random ids
+    pub fn new() -> SpanContext {
+        let span_id = Uuid::new_v4().to_simple().to_string();
+        SpanContext {
+            trace_id: Uuid::new_v4().to_simple().to_string(),
+            span_id: span_id[..16].to_owned(),
+        }
+    }
+
+    /// Returns value of `traceparent` header of a sampled request
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+}
+
+/// Client span of a single request
+#[derive(Debug)]
+pub struct Span {
+    pub context: SpanContext,
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    pub start: SystemTime,
+    pub end: SystemTime,
+
+    /// Final status code, 0 if request failed
+    pub status: u16,
+
+    /// Kind of error if request failed
+    pub error: Option<String>,
+}
+
+/// Returns nanoseconds since Unix epoch as a string, as OTLP JSON encodes 64 bit integers
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+/// Returns an OTLP key value attribute
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+impl Span {
+    /// Returns the span in OTLP JSON encoding
+    fn to_otlp(&self) -> Value {
+        let mut attributes = vec![
+            attribute("http.request.method", json!({ "stringValue": self.method })),
+            attribute("url.full", json!({ "stringValue": self.url })),
+        ];
+        if self.status != 0 {
+            attributes.push(attribute(
+                "http.response.status_code",
+                json!({ "intValue": self.status.to_string() }),
+            ));
+        }
+        if let Some(error) = &self.error {
+            attributes.push(attribute("error.type", json!({ "stringValue": error })));
+        }
+
+        // Unset status for successful requests, error otherwise
+        let failed = self.status == 0 || self.status >= 400;
+        json!({
+            "traceId": self.context.trace_id,
+            "spanId": self.context.span_id,
+            "name": self.name,
+            "kind": 3,
+            "startTimeUnixNano": unix_nanos(self.start),
+            "endTimeUnixNano": unix_nanos(self.end),
+            "attributes": attributes,
+            "status": { "code": if failed { 2 } else { 0 } },
+        })
+    }
+}
+
+/// Exports client spans to an OpenTelemetry collector over OTLP/HTTP with JSON encoding.
+/// Spans are sent in batches by a background task so requests never wait for the collector.
+pub struct SpanExporter {
+    sender: mpsc::Sender<Span>,
+    handle: JoinHandle<()>,
+}
+
+impl SpanExporter {
+    /// Starts exporting spans to supplied collector URL. `/v1/traces` is used if URL has no path.
+    pub fn start(endpoint: &str, logger: Logger) -> anyhow::Result<SpanExporter> {
+        let mut url =
+            Url::parse(endpoint).with_context(|| format!("Invalid OTLP endpoint {}", endpoint))?;
+        if url.path() == "/" {
+            url.set_path("/v1/traces");
+        }
+        let client = backend_client()
+            .timeout(EXPORT_TIMEOUT)
+            .build()
+            .context("Failed to build OTLP client")?;
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+        let handle = tokio::spawn(export_spans(client, url, receiver, logger));
+        Ok(SpanExporter { sender, handle })
+    }
+
+    /// Returns a sender to queue spans for export with
+    pub fn sender(&self) -> mpsc::Sender<Span> {
+        self.sender.clone()
+    }
+
+    /// Waits till all the queued spans are exported. Senders returned by `sender` must be
+    /// dropped before.
+    pub async fn shutdown(self) {
+        drop(self.sender);
+        let _ = self.handle.await;
+    }
+}
+
+/// Sends queued spans to collector in batches till all the senders are dropped
+async fn export_spans(
+    client: Client,
+    url: Url,
+    mut receiver: mpsc::Receiver<Span>,
+    logger: Logger,
+) {
+    let mut batch = vec![];
+    let mut closed = false;
+    while !closed {
+        // Wait for the first span of a batch, then collect more till batch is full or timeout
+        match receiver.recv().await {
+            Some(span) => batch.push(span),
+            None => break,
+        }
+        let deadline = tokio::time::Instant::now() + BATCH_TIMEOUT;
+        while batch.len() < MAX_BATCH_SIZE {
+            match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(span)) => batch.push(span),
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        let body = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [attribute("service.name", json!({ "stringValue": "stresster" }))],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "stresster", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": batch.drain(..).map(|span| span.to_otlp()).collect::<Vec<Value>>(),
+                }],
+            }],
+        });
+        let result = client
+            .post(url.clone())
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            error!(logger, "Failed to export spans : {}", e);
+        }
+    }
+}
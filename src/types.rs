@@ -1,46 +1,221 @@
+use crate::arrival::Arrival;
+use crate::report::Report;
+use crate::think_time::ThinkTime;
+use crate::thresholds::Threshold;
 use reqwest::header::HeaderMap;
+use reqwest::Proxy;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// Number of redirects followed when not specified in Data file
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Exit code of invalid input or failures like IO errors, unlike 1 which is exited with when
+/// results fail their checks
+pub const ERROR_EXIT_CODE: u8 = 2;
+
 pub type Data = Arc<RequestData>;
 pub type Map = HashMap<u16, i32>;
-pub type Countermap = Arc<Mutex<Map>>;
+pub type SharedReport = Arc<Mutex<Report>>;
 pub type Logger = Arc<slog::Logger>;
 
-#[derive(PartialEq)]
+/// Names of supported output formats
+pub const OUTPUT_FORMATS: [&str; 6] = ["table", "json", "csv", "html", "junit", "markdown"];
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum OutputFormat {
     Json,
     Table,
+    Csv,
+    Html,
+    Junit,
+    Markdown,
 }
 
 impl From<String> for OutputFormat {
     fn from(output_format: String) -> Self {
-        if output_format.to_lowercase() == "json" {
-            OutputFormat::Json
-        } else {
-            OutputFormat::Table
-        }
+        OutputFormat::from(output_format.as_str())
     }
 }
 
 impl From<&str> for OutputFormat {
     fn from(output_format: &str) -> Self {
-        if output_format.to_lowercase() == "json" {
-            OutputFormat::Json
-        } else {
-            OutputFormat::Table
+        match output_format.to_lowercase().as_str() {
+            "json" => OutputFormat::Json,
+            "csv" => OutputFormat::Csv,
+            "html" => OutputFormat::Html,
+            "junit" => OutputFormat::Junit,
+            "markdown" => OutputFormat::Markdown,
+            _ => OutputFormat::Table,
         }
     }
 }
 
+/// Destination of output in a format
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub format: OutputFormat,
+
+    /// File to write output to, stdout if not supplied
+    pub path: Option<PathBuf>,
+}
+
+/// Values supplied as command line arguments
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Outputs to produce once the run is finished
+    pub outputs: Vec<Output>,
+
+    /// Data file path
+    pub config_filename: String,
+
+    /// Number of requests to send, 0 for infinite
+    pub total_requests: i32,
+
+    /// Number of virtual users sending requests concurrently, 0 for a user per request
+    pub users: i32,
+
+    /// Requests per second to send at, as fast as users can if not supplied
+    pub rate: Option<f64>,
+
+    /// How send times are spread when sending at a fixed rate
+    pub arrival: Arrival,
+
+    /// Time a virtual user waits between its requests
+    pub think_time: Option<ThinkTime>,
+
+    /// Seed of random arrival gaps and think times, so runs can be reproduced
+    pub seed: Option<u64>,
+
+    /// Directory to write bodies of failed responses to
+    pub capture_dir: Option<PathBuf>,
+
+    /// Don't show live progress
+    pub quiet: bool,
+
+    /// Length of intervals results are aggregated in
+    pub interval: Duration,
+
+    /// CSV file to write results per interval to
+    pub timeseries_csv: Option<PathBuf>,
+
+    /// CSV file to write result of every request to
+    pub raw_log: Option<PathBuf>,
+
+    /// Address to serve live metrics in Prometheus format at
+    pub metrics_listen: Option<SocketAddr>,
+
+    /// Time to keep serving metrics after the run is finished
+    pub metrics_linger: Duration,
+
+    /// InfluxDB URL to push results per interval to, `udp://host:port` or HTTP write API
+    pub influx: Option<String>,
+
+    /// StatsD address to push results per interval to
+    pub statsd: Option<String>,
+
+    /// Add DogStatsD tags to StatsD metrics
+    pub dogstatsd: bool,
+
+    /// OpenTelemetry collector URL to export client spans to over OTLP/HTTP
+    pub otlp_endpoint: Option<String>,
+
+    /// SQLite database to record the run in
+    pub save_history: Option<PathBuf>,
+
+    /// JSON lines file to write an event of every request to
+    pub event_log: Option<PathBuf>,
+
+    /// Where and how log is written
+    pub log: LogOptions,
+}
+
+/// Where and how log is written
+#[derive(Debug, Clone)]
+pub struct LogOptions {
+    /// File to write log to, `stresster.log` if not supplied
+    pub path: Option<PathBuf>,
+
+    /// Lowest level of records written, `None` if logging is disabled
+    pub level: Option<slog::Level>,
+
+    /// Write records as JSON objects instead of plain text
+    pub json: bool,
+
+    /// Write log to stderr instead of a file
+    pub stderr: bool,
+
+    /// Append to log file instead of truncating it
+    pub append: bool,
+
+    /// Per request records are written for 1 in these many requests
+    pub sample: u64,
+}
+
 #[derive(Debug)]
 pub enum Command {
-    Increment(u16),
+    Record(Box<RequestResult>),
     Exit,
 }
 
+/// Outcome of a single request sent to the target
+#[derive(Debug, Clone, Default)]
+pub struct RequestResult {
+    /// Name of the request
+    pub endpoint: String,
+
+    /// Final status code, 0 if request failed
+    pub status: u16,
+
+    /// Kind of error if request failed or its response body couldn't be read
+    pub error: Option<String>,
+
+    /// Status codes of redirect responses followed before the final response
+    pub redirects: Vec<u16>,
+
+    /// Size of request body
+    pub bytes_sent: u64,
+
+    /// Size of response body as received on the wire
+    pub bytes_received: u64,
+
+    /// Size of response body after decompression
+    pub bytes_decoded: u64,
+
+    /// Time taken from sending request till reading whole response body
+    pub latency: Duration,
+
+    /// Time taken from sending request till receiving response headers
+    pub ttfb: Duration,
+
+    /// Time taken by DNS lookup if a new connection was opened
+    pub dns: Option<Duration>,
+
+    /// Time taken to open a new connection excluding DNS lookup and TLS handshake
+    pub connect: Option<Duration>,
+
+    /// Time taken by TLS handshake if a new HTTPS connection was opened
+    pub tls: Option<Duration>,
+
+    /// Time since start of the run when request completed
+    pub completed_at: Duration,
+
+    /// Time request was sent after its intended send time, if sent at a fixed rate
+    pub delay: Option<Duration>,
+}
+
+impl RequestResult {
+    /// Returns true if request failed or server responded with an error status code
+    pub fn is_failure(&self) -> bool {
+        self.status == 0 || self.status >= 400
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum HttpMethods {
     Get,
@@ -78,8 +253,29 @@ pub struct RequestData {
     /// URL
     pub url: String,
 
+    /// Name of the request used in the output, URL if not supplied
+    pub name: String,
+
+    /// Content of Data file as supplied
+    pub config: Value,
+
+    /// Limits results of the run are checked against
+    pub thresholds: Vec<Threshold>,
+
+    /// Send W3C trace context with every request
+    pub trace: bool,
+
     /// SSL certificate path
     pub cert_path: String,
+
+    /// Proxies to route requests through
+    pub proxies: Vec<Proxy>,
+
+    /// Maximum number of redirects to follow, 0 to not follow redirects at all
+    pub max_redirects: usize,
+
+    /// Keep cookies set by responses for subsequent requests of the same virtual user
+    pub cookies: bool,
 }
 
 impl Default for RequestData {
@@ -90,7 +286,14 @@ impl Default for RequestData {
             headers: HeaderMap::new(),
             method: HttpMethods::fromstr("get").unwrap(),
             url: "".to_owned(),
+            name: "".to_owned(),
+            config: Value::Null,
+            thresholds: vec![],
+            trace: false,
             cert_path: "".to_owned(),
+            proxies: vec![],
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cookies: false,
         }
     }
 }
@@ -1,9 +1,14 @@
 # This module contains unsecure, unoptimized, native, simple flask
 # HTTP server to serve as a test server for stresster.
 
-from flask import Flask, request
+from flask import Flask, redirect, request
+import gzip
+import os
 import random
+import re
 import sys
+import time
+import zlib
 
 IP = "0.0.0.0"
 PORT = "15000"
@@ -16,7 +21,7 @@ statuses = [200, 404, 201, 500, 302]
 @app.route("/gettest")
 def route1():
     print("route1 called")
-    print(request.json)
+    print(request.get_json(silent=True))
     print(request.headers)
     if request.headers and "code" in request.headers:
         return 'Hello, World!', request.headers["code"]
@@ -25,13 +30,59 @@ def route1():
 @app.route('/posttest', methods=["POST", "PUT", "PATCH", "DELETE"])
 def route2():
     print("route2 called")
-    print(request.json)
+    print(request.get_json(silent=True))
     print(request.headers)
     if request.headers and "code" in request.headers:
         return 'Hello, World!', request.headers["code"]
     return 'Hello, World!', random.choice(statuses)
 
+@app.route("/redirecttest")
+def route3():
+    print("route3 called")
+    return redirect("/gettest", code=302)
+
+@app.route("/compressiontest")
+def route4():
+    print("route4 called")
+    body = b"Hello, World!" * 100
+    if "double" in request.args:
+        return gzip.compress(zlib.compress(body)), 200, {"Content-Encoding": "deflate, gzip"}
+    if "gzip" in request.headers.get("Accept-Encoding", ""):
+        return gzip.compress(body), 200, {"Content-Encoding": "gzip"}
+    return body
+
+@app.route("/cookietest")
+def route5():
+    print("route5 called")
+    if "session" in request.cookies:
+        return 'Welcome back!'
+    return 'Hello, World!', 201, {"Set-Cookie": "session=stresster"}
+
+@app.route("/tracetest")
+def route6():
+    print("route6 called")
+    traceparent = request.headers.get("traceparent", "")
+    if re.fullmatch("00-[0-9a-f]{32}-[0-9a-f]{16}-01", traceparent):
+        return '', 204
+    return 'Missing trace context', 400
+
+@app.route("/slowtest")
+def route7():
+    print("route7 called")
+    time.sleep(0.2)
+    return 'Hello, World!'
+
+@app.route("/write", methods=["POST"])
+def route8():
+    print("route8 called")
+    # Stands in for InfluxDB write API and OpenTelemetry collector
+    return '', 204
+
+# Usage: server.py [true [port]] to serve HTTPS, optionally on another port
 ssl_context = None
-if len(sys.argv) == 2 and sys.argv[1] == "true":
-    ssl_context=("cert.pem", "key.pem")
+if len(sys.argv) >= 2 and sys.argv[1] == "true":
+    directory = os.path.dirname(os.path.abspath(__file__))
+    ssl_context=(os.path.join(directory, "cert.pem"), os.path.join(directory, "key.pem"))
+if len(sys.argv) == 3:
+    PORT = sys.argv[2]
 app.run(host=IP, port=PORT, ssl_context=ssl_context)